@@ -5,7 +5,11 @@ use criterion::{
 use gperftools::profiler::PROFILER;
 use gperftools::HEAP_PROFILER;
 use rand::{rngs::ThreadRng, thread_rng, Rng};
-use sp_algo::{dijkstra::*, dimacs::*, implicit_heaps::*, pairing_heap::*};
+use sp_algo::{
+    bucket_queue::*, context::*, delta_stepping::*, dijkstra::*, dimacs::*, fibonacci_heap::*,
+    implicit_heaps::*, min_plus::*, pairing_heap::*, radix_heap::*, rank_pairing_heap::*,
+    renumber::*,
+};
 use std::{fs, path::Path, process::Command, time::Duration};
 
 struct GProfiler;
@@ -61,17 +65,53 @@ macro_rules! impl_has_type_name {
     );
 }
 
+// arities without a named alias
+type DaryHeap3 = DaryHeap<3>;
+type DaryHeap6 = DaryHeap<6>;
+type DaryHeap32 = DaryHeap<32>;
+type DaryHeapSimple3 = DaryHeapSimple<3>;
+type DaryHeapSimple6 = DaryHeapSimple<6>;
+type DaryHeapSimple32 = DaryHeapSimple<32>;
+
+// pairing heap variants, the default merges the auxiliary list with multipass and children with
+// two-pass
+type PairingHeapMultipass = PairingHeap<Multipass, Multipass>;
+type PairingHeapTwoPass = PairingHeap<TwoPass, TwoPass>;
+type PairingHeapTwoPassReverse = PairingHeap<Multipass, TwoPassReverse>;
+type PairingHeapEager = PairingHeap<Multipass, TwoPass, Eager>;
+type PairingHeapEagerReverse = PairingHeap<Multipass, TwoPassReverse, Eager>;
+
 impl_has_type_name!(
     SortetList,
     BinaryHeap,
     PentaryHeap,
     OctaryHeap,
     HexadecimaryHeap,
+    IndexedBinaryHeap,
+    IndexedPentaryHeap,
     BinaryHeapSimple,
     PentaryHeapSimple,
     OctaryHeapSimple,
     HexadecimaryHeapSimple,
+    DaryHeap3,
+    DaryHeap6,
+    DaryHeap32,
+    DaryHeapSimple3,
+    DaryHeapSimple6,
+    DaryHeapSimple32,
     PairingHeap,
+    PairingHeapMultipass,
+    PairingHeapTwoPass,
+    PairingHeapTwoPassReverse,
+    PairingHeapEager,
+    PairingHeapEagerReverse,
+    ArenaPairingHeap,
+    RadixHeap,
+    BucketQueue,
+    FibonacciHeap,
+    RankPairingHeap,
+    Hashed,
+    Dense,
 );
 
 pub fn cmp_sp_queries(c: &mut Criterion) {
@@ -124,6 +164,75 @@ pub fn cmp_sp_queries(c: &mut Criterion) {
                 criterion::BatchSize::LargeInput,
             );
         });
+        group.bench_with_input(BenchmarkId::new("BiParallel", &size), &size, |b, &size| {
+            b.iter_batched(
+                || {
+                    (
+                        PentaryHeap::init_dijkstra(
+                            rng.gen_range(0..size).try_into().unwrap(),
+                            size,
+                        ),
+                        PentaryHeap::init_dijkstra(
+                            rng.gen_range(0..size).try_into().unwrap(),
+                            size,
+                        ),
+                    )
+                },
+                |(source, target)| sp_bi_parallel(source, target, &bigraph),
+                criterion::BatchSize::LargeInput,
+            );
+        });
+        let mut context: QueryContext<PentaryHeap> = QueryContext::new(size);
+        group.bench_with_input(BenchmarkId::new("NaivContext", &size), &size, |b, &size| {
+            b.iter_batched(
+                || {
+                    (
+                        rng.gen_range(0..size).try_into().unwrap(),
+                        rng.gen_range(0..size).try_into().unwrap(),
+                    )
+                },
+                |(source, target)| context.sp_naiv(source, target, &graph),
+                criterion::BatchSize::SmallInput,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("BiContext", &size), &size, |b, &size| {
+            b.iter_batched(
+                || {
+                    (
+                        rng.gen_range(0..size).try_into().unwrap(),
+                        rng.gen_range(0..size).try_into().unwrap(),
+                    )
+                },
+                |(source, target)| context.sp_bi(source, target, &bigraph),
+                criterion::BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+pub fn cmp_min_plus(c: &mut Criterion) {
+    let rng = &mut thread_rng();
+    let mut group = c.benchmark_group("MinPlus");
+    let mut kernels = vec![Kernel::Scalar, Kernel::detect()];
+    kernels.dedup();
+    for size in [64, 256, 1024] {
+        let mut matrix =
+            || -> Vec<u32> { (0..size * size).map(|_| rng.gen_range(0..1000)).collect() };
+        let (a, b) = (matrix(), matrix());
+        for kernel in kernels.iter() {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", kernel), size),
+                &size,
+                |bench, &size| {
+                    bench.iter_batched_ref(
+                        || vec![u32::MAX; size * size],
+                        |c| kernel.min_plus(c, &a, &b, size, size, size),
+                        criterion::BatchSize::LargeInput,
+                    );
+                },
+            );
+        }
     }
     group.finish();
 }
@@ -144,16 +253,92 @@ pub fn cmp_sssp(c: &mut Criterion) {
         let n: usize = load_max_vertex(Path::new(&format!("./data/{}.co", region))).into();
         let size = n + 1;
         let graph: NeighborList = preprocess_graph(region, size);
-        benchmark::<BinaryHeap>(rng, size, &graph, &mut group);
-        benchmark::<PentaryHeap>(rng, size, &graph, &mut group);
-        benchmark::<OctaryHeap>(rng, size, &graph, &mut group);
-        benchmark::<HexadecimaryHeap>(rng, size, &graph, &mut group);
-        benchmark::<BinaryHeapSimple>(rng, size, &graph, &mut group);
-        benchmark::<PentaryHeapSimple>(rng, size, &graph, &mut group);
-        benchmark::<OctaryHeapSimple>(rng, size, &graph, &mut group);
-        benchmark::<HexadecimaryHeapSimple>(rng, size, &graph, &mut group);
-        benchmark::<PairingHeap>(rng, size, &graph, &mut group);
-        benchmark::<SortetList>(rng, size, &graph, &mut group);
+        benchmark::<BinaryHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PentaryHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<OctaryHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<HexadecimaryHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<IndexedBinaryHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<IndexedPentaryHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<BinaryHeapSimple, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PentaryHeapSimple, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<OctaryHeapSimple, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<HexadecimaryHeapSimple, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<DaryHeap3, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<DaryHeap6, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<DaryHeap32, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<DaryHeapSimple3, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<DaryHeapSimple6, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<DaryHeapSimple32, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PairingHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PairingHeapMultipass, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PairingHeapTwoPass, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PairingHeapTwoPassReverse, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PairingHeapEager, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<PairingHeapEagerReverse, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<ArenaPairingHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<SortetList, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<RadixHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<BucketQueue, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<FibonacciHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<RankPairingHeap, Hashed>(rng, size, &graph, &mut group);
+        benchmark::<BinaryHeap, Dense>(rng, size, &graph, &mut group);
+        benchmark::<PentaryHeap, Dense>(rng, size, &graph, &mut group);
+        benchmark::<BinaryHeapSimple, Dense>(rng, size, &graph, &mut group);
+        benchmark::<PentaryHeapSimple, Dense>(rng, size, &graph, &mut group);
+        benchmark::<PairingHeap, Dense>(rng, size, &graph, &mut group);
+        let delta = default_delta(&graph);
+        group.bench_with_input(
+            BenchmarkId::new("DeltaStepping", &size),
+            &size,
+            |b, &size| {
+                b.iter_batched(
+                    || rng.gen_range(0..size).try_into().unwrap(),
+                    |source| delta_stepping(source, &graph, delta),
+                    criterion::BatchSize::SmallInput,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+pub fn cmp_orders(c: &mut Criterion) {
+    let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
+    let smaller_regions = [
+        "USA", "CTR", "W", "E", "LKS", "CAL", "NE", "NW", "FLA", "COL", "BAY", "NY",
+    ];
+    let rng = &mut thread_rng();
+    let mut group = c.benchmark_group("Orders");
+    group
+        .measurement_time(Duration::from_secs(600))
+        .sample_size(50)
+        .sampling_mode(SamplingMode::Flat)
+        .plot_config(plot_config);
+    for region in smaller_regions {
+        let path = format!("./data/{}.co", region);
+        let n: usize = load_max_vertex(Path::new(&path)).into();
+        let size = n + 1;
+        let bigraph = preprocess_bigraph(region, size);
+        // the unused last vertex has no coordinates, put it in the corner
+        let coordinates: Vec<Coordinates> = load_coordinates(Path::new(&path))
+            .chain(std::iter::once(Coordinates { x: 0, y: 0 }))
+            .collect();
+        let orders = [
+            ("Original", Renumbering::identity(size)),
+            ("BFS", bfs_order(&bigraph.forward, Vertex(1))),
+            ("DFS", dfs_order(&bigraph.forward, Vertex(1))),
+            ("Hilbert", hilbert_order(&coordinates)),
+        ];
+        for (name, map) in orders {
+            let reordered = Reordered::new(&bigraph, map);
+            group.bench_with_input(BenchmarkId::new(name, &size), &size, |b, &size| {
+                b.iter_batched(
+                    || rng.gen_range(0..size).try_into().unwrap(),
+                    |source| reordered.sssp::<PentaryHeap>(source).into_inner(),
+                    criterion::BatchSize::LargeInput,
+                );
+            });
+        }
     }
     group.finish();
 }
@@ -161,7 +346,7 @@ pub fn cmp_sssp(c: &mut Criterion) {
 criterion_group! {
     name = benches;
     config = Criterion::default().with_profiler(GProfiler);
-    targets =  cmp_sssp,cmp_sp_queries
+    targets =  cmp_sssp,cmp_sp_queries,cmp_orders,cmp_min_plus
 }
 criterion_main!(benches);
 
@@ -178,20 +363,21 @@ fn preprocess_bigraph(region: &str, n: usize) -> DicirectionalList<NeighborList>
 }
 
 #[inline]
-fn benchmark<Q>(
+fn benchmark<Q, B>(
     rng: &mut ThreadRng,
     size: usize,
     graph: &NeighborList,
     group: &mut BenchmarkGroup<WallTime>,
 ) where
     Q: PriorityQueue + HasTypeName + InitDijkstra,
+    B: Backend + HasTypeName,
 {
     group.bench_with_input(
-        BenchmarkId::new(format!("{}", Q::type_name()), &size),
+        BenchmarkId::new(format!("{}/{}", Q::type_name(), B::type_name()), &size),
         &size,
         |b, &size| {
             b.iter_batched(
-                || Q::init_dijkstra(rng.gen_range(0..size).try_into().unwrap(), size),
+                || Q::init_dijkstra_with::<B>(rng.gen_range(0..size).try_into().unwrap(), size),
                 |queue| sssp(queue, graph),
                 criterion::BatchSize::LargeInput,
             );
@@ -1,7 +1,10 @@
 use iai_callgrind::{library_benchmark, library_benchmark_group, main};
 use paste::paste;
 // use rand::{rngs::ThreadRng, thread_rng, Rng};
-use sp_algo::{dijkstra::*, dimacs::*, implicit_heaps::*, pairing_heap::*};
+use sp_algo::{
+    bucket_queue::*, dijkstra::*, dimacs::*, fibonacci_heap::*, implicit_heaps::*, pairing_heap::*,
+    radix_heap::*, rank_pairing_heap::*,
+};
 use std::path::Path;
 
 #[allow(dead_code)]
@@ -9,6 +12,11 @@ const SMALLER_REGIONS: [&str; 12] = [
     "USA", "CTR", "W", "E", "LKS", "CAL", "NE", "NW", "FLA", "COL", "BAY", "NY",
 ];
 
+// arities without a named alias
+type DaryHeap3 = DaryHeap<3>;
+type DaryHeap6 = DaryHeap<6>;
+type DaryHeap32 = DaryHeap<32>;
+
 trait HasTypeName {
     fn type_name() -> &'static str;
 }
@@ -32,11 +40,21 @@ impl_has_type_name!(
     PentaryHeap,
     OctaryHeap,
     HexadecimaryHeap,
+    IndexedBinaryHeap,
+    IndexedPentaryHeap,
     BinaryHeapSimple,
     PentaryHeapSimple,
     OctaryHeapSimple,
     HexadecimaryHeapSimple,
+    DaryHeap3,
+    DaryHeap6,
+    DaryHeap32,
     PairingHeap,
+    ArenaPairingHeap,
+    RadixHeap,
+    BucketQueue,
+    FibonacciHeap,
+    RankPairingHeap,
 );
 
 #[inline]
@@ -46,11 +64,12 @@ fn preprocess_graph(region: &str, n: usize) -> NeighborList {
 }
 
 #[inline]
-fn benchmark<Q>(size: usize, graph: &NeighborList)
+fn benchmark<Q, B>(size: usize, graph: &NeighborList)
 where
     Q: PriorityQueue + HasTypeName + InitDijkstra,
+    B: Backend,
 {
-    let queue = Q::init_dijkstra(Vertex(1), size);
+    let queue = Q::init_dijkstra_with::<B>(Vertex(1), size);
     sssp(queue, graph);
 }
 
@@ -64,15 +83,18 @@ fn setup(region: &str) -> (NeighborList, usize) {
 
 macro_rules! run {
     ($Q:ident) => {
+        run!($Q, Hashed, "");
+    };
+    ($Q:ident, $B:ident, $suffix:literal) => {
         paste! {
             #[library_benchmark]
             #[bench::in_ny(setup("NY"))]
             #[bench::in_ne(setup("NE"))]
             #[bench::in_e(setup("E"))]
             #[bench::in_usa(setup("USA"))]
-            fn [<run_ $Q:lower>](input: (NeighborList, usize))  {
+            fn [<run_ $Q:lower $suffix>](input: (NeighborList, usize))  {
                 let (graph, size) = input;
-                benchmark::<$Q>(size, &graph);
+                benchmark::<$Q, $B>(size, &graph);
             }
         }
     };
@@ -82,18 +104,36 @@ run!(BinaryHeap);
 run!(PentaryHeap);
 run!(OctaryHeap);
 run!(HexadecimaryHeap);
+run!(IndexedBinaryHeap);
+run!(IndexedPentaryHeap);
 run!(BinaryHeapSimple);
 run!(PentaryHeapSimple);
 run!(OctaryHeapSimple);
 run!(HexadecimaryHeapSimple);
+run!(DaryHeap3);
+run!(DaryHeap6);
+run!(DaryHeap32);
 run!(PairingHeap);
+run!(ArenaPairingHeap);
+run!(RadixHeap);
+run!(BucketQueue);
+run!(FibonacciHeap);
+run!(RankPairingHeap);
+run!(BinaryHeap, Dense, "_dense");
+run!(PentaryHeap, Dense, "_dense");
+run!(BinaryHeapSimple, Dense, "_dense");
+run!(PentaryHeapSimple, Dense, "_dense");
 
 library_benchmark_group!(
     name = sssp;
     compare_by_id = true;
     benchmarks = run_binaryheap, run_pentaryheap, run_octaryheap, run_hexadecimaryheap,
-    run_binaryheapsimple, run_pentaryheapsimple, run_octaryheapsimple,
-    run_hexadecimaryheapsimple,
+    run_indexedbinaryheap, run_indexedpentaryheap, run_binaryheapsimple, run_pentaryheapsimple,
+    run_octaryheapsimple, run_hexadecimaryheapsimple, run_daryheap3, run_daryheap6, run_daryheap32,
+    run_radixheap,
+    run_bucketqueue, run_fibonacciheap, run_rankpairingheap, run_arenapairingheap,
+    run_binaryheap_dense, run_pentaryheap_dense, run_binaryheapsimple_dense,
+    run_pentaryheapsimple_dense,
 );
 
 main!(library_benchmark_groups = sssp);
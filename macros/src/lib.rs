@@ -2,88 +2,228 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::DeriveInput;
-use syn::{parse_macro_input, Fields};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Field, Fields, GenericArgument, PathArguments,
+    Result, Type, TypePath,
+};
 
-#[proc_macro_derive(PriorityQueue)]
+/// Derives `PriorityQueue` for an implicit heap.
+///
+/// The heap field is a `Vec` of items with a `key` and a `value` field, by default the field named
+/// `inner` or the one marked with `#[pq(heap)]`. An optional position index is the field named
+/// `lookup` or the one marked with `#[pq(lookup)]`. Key and value types are taken from the
+/// generic arguments of the item type, defaulting to `u32` and `Vertex`, and can be overridden
+/// with `#[pq(key = .., value = ..)]` on the struct. The struct has to implement `Heapify`.
+#[proc_macro_derive(PriorityQueue, attributes(pq))]
 pub fn derive_answer_fn(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    derive_priority_queue(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Settings given by `#[pq(..)]` attributes.
+#[derive(Default)]
+struct Options {
+    key: Option<Type>,
+    value: Option<Type>,
+}
 
-    if let syn::Data::Struct(ref data) = input.data {
-        if let Fields::Named(ref fields) = data.fields {
-            let lookup = fields.named.iter().find(|field| {
-                let name = field.ident.as_ref().unwrap();
-                name.to_string() == "lookup"
-            });
-
-            let name = input.ident;
-            let (insert, update, remove) = if let Some(_) = lookup {
-                (
-                    quote!(
-                        self.lookup.insert(value,end);
-                        #[cfg(debug_assertions)] dbg!(self.lookup.capacity());
-                    ),
-                    quote!(
-                        let last = self.inner
-                            .last()
-                            .expect("pop_min called even though heap was empty")
-                            .clone()
-                            .value;
-                        self.lookup.insert(last, 0);
-                    ),
-                    quote!(
-                        self.lookup.remove(&min.value);
-                        #[cfg(debug_assertions)] dbg!(self.lookup.capacity());
-                    ),
-                )
+fn derive_priority_queue(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let Data::Struct(ref data) = input.data else {
+        return Err(Error::new(
+            input.ident.span(),
+            "PriorityQueue can only be derived for structs",
+        ));
+    };
+    let Fields::Named(ref fields) = data.fields else {
+        return Err(Error::new(
+            input.ident.span(),
+            "PriorityQueue can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut options = Options::default();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("pq")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("key") {
+                options.key = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("value") {
+                options.value = Some(meta.value()?.parse()?);
+                Ok(())
             } else {
-                (quote!(), quote!(), quote!())
-            };
+                Err(meta.error("expected `key` or `value`"))
+            }
+        })?;
+    }
 
-            return TokenStream::from(quote!(
-                impl PriorityQueue for #name {
-                    type RefType = Self::Value;
+    let heap = find_field(fields.named.iter(), "heap", "inner")?.ok_or_else(|| {
+        Error::new(
+            input.ident.span(),
+            "expected a field named `inner` or marked with `#[pq(heap)]`",
+        )
+    })?;
+    let lookup = find_field(fields.named.iter(), "lookup", "lookup")?;
 
-                    type Key = u32;
+    let item = item_type(&heap.ty)?;
+    let (key, value) = item_arguments(&item);
+    let key = options
+        .key
+        .or(key)
+        .unwrap_or_else(|| syn::parse_quote!(u32));
+    let value = options
+        .value
+        .or(value)
+        .unwrap_or_else(|| syn::parse_quote!(Vertex));
+    let item = expression_path(item);
+    let heap = heap.ident.as_ref();
 
-                    type Value = Vertex;
+    let (insert, update, remove, reset) = if let Some(lookup) = lookup {
+        let lookup = lookup.ident.as_ref();
+        (
+            quote!(self.#lookup.insert(value, end);),
+            quote!(
+                let last = self.#heap
+                    .last()
+                    .expect("pop_min called even though heap was empty")
+                    .value;
+                self.#lookup.insert(last, 0);
+            ),
+            quote!(self.#lookup.remove(&min.value);),
+            quote!(
+                self.#lookup.clear();
+                self.#lookup.insert(source.into(), 0);
+            ),
+        )
+    } else {
+        (quote!(), quote!(), quote!(), quote!())
+    };
 
-                    #[inline]
-                    fn is_empty(&self) -> bool {
-                        self.inner.is_empty()
-                    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote!(
+        impl #impl_generics PriorityQueue for #name #ty_generics #where_clause {
+            type RefType = Self::Value;
 
-                    #[inline]
-                    fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
-                        if self.is_empty() {
-                            return None;
-                        }
-                        #update
-                        let min = self.inner.swap_remove(0);
-                        #remove
-                        self.bubble_down();
-                        return Some((min.key, min.value));
-                    }
+            type Key = #key;
+
+            type Value = #value;
+
+            #[inline]
+            fn is_empty(&self) -> bool {
+                self.#heap.is_empty()
+            }
+
+            #[inline]
+            fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
+                if self.is_empty() {
+                    return None;
+                }
+                #update
+                let min = self.#heap.swap_remove(0);
+                #remove
+                Heapify::bubble_down(self);
+                Some((min.key, min.value))
+            }
+
+            #[inline]
+            fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
+                self.#heap.push(#item { key, value });
+                let end = self.#heap.len() - 1;
+                #insert
+                Heapify::bubble_up(self, end);
+                value
+            }
 
-                    #[inline]
-                    fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
-                        self.inner.push(Item { key, value });
-                        let end = self.inner.len() - 1;
-                        #insert
-                        self.bubble_up(end);
-                        value
+            #[inline]
+            fn reset(&mut self, source: Vertex) {
+                self.#heap.clear();
+                self.#heap.push(#item {
+                    key: Self::Key::from(0u32),
+                    value: source.into(),
+                });
+                #reset
+            }
+        }
+    ))
+}
+
+/// Finds the field marked with `#[pq(marker)]`, falling back to the field called `name`.
+fn find_field<'a>(
+    fields: impl Iterator<Item = &'a Field> + Clone,
+    marker: &str,
+    name: &str,
+) -> Result<Option<&'a Field>> {
+    let mut marked = None;
+    for field in fields.clone() {
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("pq")) {
+            let mut found = false;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("heap") || meta.path.is_ident("lookup") {
+                    found |= meta.path.is_ident(marker);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `heap` or `lookup`"))
+                }
+            })?;
+            if found && marked.replace(field).is_some() {
+                return Err(Error::new(
+                    field.span(),
+                    format!("only one field can be marked with `#[pq({marker})]`"),
+                ));
+            }
+        }
+    }
+    if marked.is_some() {
+        return Ok(marked);
+    }
+    Ok(fields
+        .into_iter()
+        .find(|field| field.ident.as_ref().is_some_and(|ident| ident == name)))
+}
+
+/// Returns the item type `T` of a heap field of type `Vec<T>`.
+fn item_type(ty: &Type) -> Result<TypePath> {
+    if let Type::Path(TypePath { path, .. }) = ty {
+        if let Some(segment) = path.segments.last() {
+            if segment.ident == "Vec" {
+                if let PathArguments::AngleBracketed(ref args) = segment.arguments {
+                    if let Some(GenericArgument::Type(Type::Path(item))) = args.args.first() {
+                        return Ok(item.clone());
                     }
                 }
-            ));
+            }
         }
     }
+    Err(Error::new(
+        ty.span(),
+        "expected the heap to be a `Vec` of items with a `key` and a `value` field",
+    ))
+}
 
-    // Catchall if we don't match on the structure we don't want
-    TokenStream::from(
-        syn::Error::new(
-            input.ident.span(),
-            "Only structs with named fields and one 'subtrees' field can match",
-        )
-        .to_compile_error(),
-    )
+/// Returns the first two generic arguments of the item type, which are its key and value.
+fn item_arguments(item: &TypePath) -> (Option<Type>, Option<Type>) {
+    let Some(segment) = item.path.segments.last() else {
+        return (None, None);
+    };
+    let PathArguments::AngleBracketed(ref args) = segment.arguments else {
+        return (None, None);
+    };
+    let mut types = args.args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    });
+    (types.next(), types.next())
+}
+
+/// Turns a type path like `Item<u64>` into a path usable in expressions like `Item::<u64>`.
+fn expression_path(mut item: TypePath) -> TypePath {
+    for segment in item.path.segments.iter_mut() {
+        if let PathArguments::AngleBracketed(ref mut args) = segment.arguments {
+            args.colon2_token = Some(Default::default());
+        }
+    }
+    item
 }
@@ -1,33 +1,86 @@
 use std::{
-    fs::File,
-    io::{self, stdout, BufWriter, Error, Read, Seek, SeekFrom, Write},
-    path::Path,
+    fs::{self, File, OpenOptions},
+    io::{self, stdout, Error, Write},
+    ops::RangeInclusive,
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicU32, Ordering},
+        atomic::{AtomicU32, AtomicUsize, Ordering},
         Mutex,
     },
+    time::{Duration, Instant},
     usize,
 };
 
 use chrono::{DateTime, Local};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
-use crate::dijkstra::{DicirectionalList, OwnedLookup};
+use crate::dijkstra::{Hashed, InitDijkstra};
 use crate::{
+    checkpoint::{checksum, fingerprint, Bitmap, Manifest},
     dijkstra::{sssp, Dijkstra, NeighborList},
-    dimacs::{CostMatrix, Vertex},
-    implicit_heaps::PentaryHeap,
+    dimacs::{CostMatrix, CostMatrixWriter, Header, Vertex, UNDEFINED},
+    min_plus::{closure, min_plus},
 };
 
-/// Size of each block for block-wise operations.
-const BLOCK_SIZE: usize = 4096 * 3;
-/// Nerf factor used to run test in fasible time. Set to 1 to run fill algorithm.
-const NERF_FACTOR: usize = 200;
+/// Default size of each block for the blocked Warshall-Floyd algorithm.
+pub const DEFAULT_BLOCK_SIZE: usize = 4096 * 3;
+/// File name of the checkpoint manifest of `apsp` in the output directory.
+pub const APSP_MANIFEST: &str = "apsp.manifest";
+/// File name of the checkpoint manifest of `warshall_floyd` in the output directory.
+pub const WARSHALL_FLOYD_MANIFEST: &str = "warshall_floyd.manifest";
+/// Minimum time between two saves of a checkpoint manifest while items complete.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Represents the source vertices whose rows of the cost matrix are computed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Sources {
+    /// Every vertex of the graph.
+    #[default]
+    All,
+    /// The vertices from the first to the last one.
+    Range(RangeInclusive<Vertex>),
+    /// The given vertices.
+    Vertices(Vec<Vertex>),
+}
+
+impl Sources {
+    /// Returns the rows of the sources in a matrix of `size` x `size`, failing if a source is
+    /// not part of it.
+    fn rows(&self, size: usize) -> Result<Vec<usize>, io::Error> {
+        // the undefined vertex has no row
+        let row = |v: Vertex| {
+            (v != UNDEFINED && usize::from(v) < size)
+                .then(|| usize::from(v))
+                .ok_or_else(|| {
+                    Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "sources have to be vertices of the graph",
+                    )
+                })
+        };
+        match self {
+            Sources::All => Ok((0..size).collect()),
+            // both ends are checked before the rows in between are listed
+            Sources::Range(range) => Ok((row(*range.start())?..=row(*range.end())?).collect()),
+            Sources::Vertices(vertices) => vertices.iter().map(|&v| row(v)).collect(),
+        }
+    }
+}
 
-/// Wrapper struct for transposed matrix.
-struct Transpose(Vec<u32>);
-/// Wrapper struct for matrix.
-struct Matrix(Vec<u32>);
+impl From<RangeInclusive<Vertex>> for Sources {
+    #[inline]
+    fn from(range: RangeInclusive<Vertex>) -> Self {
+        Sources::Range(range)
+    }
+}
+
+impl From<Vec<Vertex>> for Sources {
+    #[inline]
+    fn from(vertices: Vec<Vertex>) -> Self {
+        Sources::Vertices(vertices)
+    }
+}
 
 /// Convert a slice of u32 to a slice of u8.
 fn as_u8_slice(v: &[u32]) -> &[u8] {
@@ -46,344 +99,493 @@ fn as_u8_slice_mut(v: &mut [u32]) -> &mut [u8] {
     }
 }
 
-/// Convert graph to matrix.
-fn graph2matrix(graph: &NeighborList, row: usize, col: usize) -> Vec<u32> {
-    let mut matrix = vec![u32::MAX; BLOCK_SIZE * BLOCK_SIZE];
-    let row_start = row * BLOCK_SIZE;
-    let col_start = col * BLOCK_SIZE;
-    let col_end = col_start + BLOCK_SIZE;
-    for i in 0..BLOCK_SIZE {
-        matrix[i * BLOCK_SIZE + i] = 0;
-        for e in graph[row_start + i].iter() {
+/// Represents a square matrix of `num_blocks` x `num_blocks` blocks, each stored contiguously
+/// in row-major order in a swap file.
+struct Blocks {
+    file: File,
+    block_size: usize,
+    num_blocks: usize,
+}
+
+impl Blocks {
+    /// Byte offset of the block in block row `i` and block column `j`.
+    #[inline]
+    fn offset(&self, i: usize, j: usize) -> u64 {
+        ((i * self.num_blocks + j) * self.block_size * self.block_size * std::mem::size_of::<u32>())
+            as u64
+    }
+
+    fn read(&self, i: usize, j: usize) -> Result<Vec<u32>, io::Error> {
+        let mut block = vec![0; self.block_size * self.block_size];
+        self.file
+            .read_exact_at(as_u8_slice_mut(&mut block), self.offset(i, j))?;
+        Ok(block)
+    }
+
+    fn write(&self, i: usize, j: usize, block: &[u32]) -> Result<(), io::Error> {
+        self.file
+            .write_all_at(as_u8_slice(block), self.offset(i, j))
+    }
+
+    /// Reads row `r` of the block in block row `i` and block column `j` into `buf`.
+    fn read_row(&self, i: usize, j: usize, r: usize, buf: &mut [u32]) -> Result<(), io::Error> {
+        let offset = self.offset(i, j) + (r * self.block_size * std::mem::size_of::<u32>()) as u64;
+        self.file.read_exact_at(as_u8_slice_mut(buf), offset)
+    }
+}
+
+/// Convert the arcs of the graph between the vertices of block `row` and block `col` into a
+/// block. Vertices beyond the graph pad the last blocks and are only connected to themselves.
+fn graph2block(graph: &NeighborList, row: usize, col: usize, block_size: usize) -> Vec<u32> {
+    let mut block = vec![u32::MAX; block_size * block_size];
+    let row_start = row * block_size;
+    let col_start = col * block_size;
+    for i in 0..block_size {
+        if row == col {
+            block[i * block_size + i] = 0;
+        }
+        for e in graph.get(row_start + i).into_iter().flatten() {
             let j = usize::from(e.to);
-            if j < col_end && col_start <= j {
-                matrix[i * BLOCK_SIZE + j] = e.weight;
+            if (col_start..col_start + block_size).contains(&j) {
+                let cell = &mut block[i * block_size + j - col_start];
+                *cell = u32::min(*cell, e.weight);
             }
         }
     }
-    matrix
+    block
 }
 
-/// Transpose a matrix.
-fn transpose(a: &Matrix) -> Transpose {
-    let mut b = Vec::with_capacity(BLOCK_SIZE * BLOCK_SIZE);
-    unsafe { b.set_len(BLOCK_SIZE * BLOCK_SIZE) }
-    transpose::transpose(&a.0, &mut b, BLOCK_SIZE, BLOCK_SIZE);
-    Transpose(b)
+/// A trait for files whose written data can be flushed to disk.
+trait SyncData {
+    fn sync_data(&self) -> Result<(), io::Error>;
 }
 
-/// Cross two blocks for block-wise Warshall-Floyd algorithm.
-///NOTE:this function can further be optimized by tiling
-///
-/// # Arguments
-///
-/// * `a` - Matrix for the first block.
-/// * `b` - Transposed matrix of second block.
-fn wf_block(a: Matrix, b: &Transpose) -> Matrix {
-    let mut a = a;
-    for k in 0..BLOCK_SIZE {
-        for i in 0..BLOCK_SIZE {
-            for j in 0..BLOCK_SIZE {
-                a.0[BLOCK_SIZE * i + j] = u32::min(
-                    a.0[BLOCK_SIZE * i + j],
-                    a.0[BLOCK_SIZE * k + i] + b.0[BLOCK_SIZE * k + j],
-                );
-            }
+impl SyncData for File {
+    #[inline]
+    fn sync_data(&self) -> Result<(), io::Error> {
+        File::sync_data(self)
+    }
+}
+
+impl SyncData for CostMatrixWriter {
+    #[inline]
+    fn sync_data(&self) -> Result<(), io::Error> {
+        self.sync()
+    }
+}
+
+/// Represents the progress of a checkpointed computation, saving its manifest from time to time.
+struct Progress {
+    state: Mutex<(Manifest, Instant)>,
+    path: PathBuf,
+    /// Number of items left to process before the run is interrupted.
+    budget: AtomicUsize,
+}
+
+impl Progress {
+    fn new(manifest: Manifest, path: PathBuf, budget: usize) -> Self {
+        Self {
+            state: Mutex::new((manifest, Instant::now())),
+            path,
+            budget: AtomicUsize::new(budget),
+        }
+    }
+
+    #[inline]
+    fn is_done(&self, i: usize) -> bool {
+        self.state.lock().unwrap().0.done.get(i)
+    }
+
+    /// Claims an item from the budget, failing once the run has to be interrupted.
+    #[inline]
+    fn claim(&self) -> Result<(), io::Error> {
+        self.budget
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |budget| {
+                budget.checked_sub(1)
+            })
+            .map(|_| ())
+            .map_err(|_| Error::new(io::ErrorKind::Interrupted, "computation was interrupted"))
+    }
+
+    /// Marks item `i` as completed, the data of its items is synced to `file` before the
+    /// manifest is saved.
+    fn complete(&self, i: usize, checksum: u64, file: &impl SyncData) -> Result<(), io::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.0.complete(i, checksum);
+        if state.1.elapsed() >= CHECKPOINT_INTERVAL {
+            file.sync_data()?;
+            state.0.save(&self.path)?;
+            state.1 = Instant::now();
         }
+        Ok(())
+    }
+
+    /// Saves the manifest after syncing `file`, moving on to the next stage if `advance` is set.
+    fn save(&self, file: &impl SyncData, advance: bool) -> Result<(), io::Error> {
+        let mut state = self.state.lock().unwrap();
+        file.sync_data()?;
+        if advance {
+            state.0.advance();
+        }
+        state.0.save(&self.path)?;
+        state.1 = Instant::now();
+        Ok(())
+    }
+
+    #[inline]
+    fn into_manifest(self) -> Manifest {
+        self.state.into_inner().unwrap().0
     }
-    a
+
+    /// Saves the manifest if the run was interrupted, so no completed item is lost.
+    fn interrupted<T>(
+        &self,
+        file: &impl SyncData,
+        result: Result<T, io::Error>,
+    ) -> Result<T, io::Error> {
+        if result.is_err() {
+            self.save(file, false)?;
+        }
+        result
+    }
+}
+
+/// Loads the checkpoint of the computation from `dir` or starts a new one with a timestamped
+/// output file if there is none or it belongs to another computation.
+fn resume(
+    path: &Path,
+    algorithm: &str,
+    (size, block_size): (usize, usize),
+    graph: &NeighborList,
+    items: usize,
+) -> Result<Manifest, io::Error> {
+    let graph = fingerprint(graph);
+    if let Some(manifest) = Manifest::load(path)? {
+        if manifest.matches(algorithm, size, block_size, graph, items) {
+            return Ok(manifest);
+        }
+    }
+    let current_local: DateTime<Local> = Local::now();
+    let timestamp = current_local.format("%Y%m%d%H%M%S");
+    let output = format!("costmatrix_{}.cost", timestamp);
+    let manifest = Manifest::new(algorithm, size, block_size, graph, output, items);
+    manifest.save(path)?;
+    Ok(manifest)
 }
 
-/// Calculate all-pairs shortest paths using Warshall-Floyd algorithm.
+/// Opens the file at `path` without truncating it, so it has at least `len` values.
+fn open_resumable(path: &Path, len: usize) -> Result<File, io::Error> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    let bytes = (len * std::mem::size_of::<u32>()) as u64;
+    if file.metadata()?.len() < bytes {
+        file.set_len(bytes)?;
+    }
+    Ok(file)
+}
+
+/// Calculate all-pairs shortest paths using the blocked Warshall-Floyd algorithm.
+///
+/// The matrix is split into blocks of `block_size` x `block_size` that are swapped to a file,
+/// so only a few blocks per thread have to fit into memory. For every block `k` on the
+/// diagonal, the diagonal block is closed first, then the blocks in row and column `k` are
+/// relaxed over it and finally all remaining blocks over the blocks of row and column `k`.
+///
+/// Completed blocks of each round are recorded in a checkpoint manifest in `dir`, an
+/// interrupted run resumes from it when called again with the same arguments.
 ///
 /// # Arguments
 ///
 /// * `size` - Number of Vertices.
-/// * `graph` - Graph represented as a directional list of neighbor lists.
+/// * `graph` - Graph represented as a neighbor list.
+/// * `block_size` - Number of vertices per block.
 /// * `dir` - Path to the directory for storing the result file.
 pub fn warshall_floyd(
     size: usize,
-    graph: &DicirectionalList<NeighborList>,
+    graph: &NeighborList,
+    block_size: usize,
+    dir: &Path,
+) -> Result<CostMatrix, io::Error> {
+    warshall_floyd_with_budget(size, graph, block_size, dir, usize::MAX)
+}
+
+/// Runs [`warshall_floyd`], interrupting it after `budget` blocks.
+fn warshall_floyd_with_budget(
+    size: usize,
+    graph: &NeighborList,
+    block_size: usize,
     dir: &Path,
+    budget: usize,
 ) -> Result<CostMatrix, io::Error> {
     if !dir.is_dir() {
         return Err(Error::new(
             io::ErrorKind::InvalidInput,
-            "apsp expects a dir",
+            "warshall_floyd expects a dir",
         ));
     }
-    let current_local: DateTime<Local> = Local::now();
-    let timestamp = current_local.format("%Y%m%d%H%M%S");
-
-    let ref file_name = format!("{}/costmatrix_{}", dir.to_str().unwrap(), timestamp);
-    let file_name = Path::new(file_name);
-
-    let (mut num_blocks, rem) = (size / BLOCK_SIZE, size % BLOCK_SIZE);
-    if rem > 0 {
-        num_blocks = num_blocks + 1;
-    }
-    let num_blocks = num_blocks;
-    dbg!(num_blocks * num_blocks);
-
-    //init swaps
-    let mut swaps: Vec<Mutex<Option<File>>> = Vec::with_capacity(num_blocks * num_blocks);
-    for _ in 0..swaps.capacity() {
-        swaps.push(Mutex::new(None));
+    if block_size == 0 {
+        return Err(Error::new(
+            io::ErrorKind::InvalidInput,
+            "block size has to be positive",
+        ));
     }
-    for i in 0..num_blocks {
-        for j in 0..num_blocks {
-            *swaps[num_blocks * i + j].lock().unwrap() = Some(tempfile::tempfile().unwrap());
-        }
+    let num_blocks = size.div_ceil(block_size);
+    let manifest_path = dir.join(WARSHALL_FLOYD_MANIFEST);
+    let mut manifest = resume(
+        &manifest_path,
+        "warshall_floyd",
+        (size, block_size),
+        graph,
+        num_blocks * num_blocks,
+    )?;
+    let file_name = dir.join(&manifest.output);
+    let swap_name = dir.join(format!("{}.blocks", manifest.output));
+    // stage 0 writes the graph, stage k + 1 is round k, stage num_blocks + 1 exports the result
+    if manifest.stage == num_blocks + 2 {
+        return CostMatrix::open(&file_name);
     }
-
-    //first round (k = 0)
-    {
-        let wkkt;
-        {
-            let mut wkk = Matrix(graph2matrix(&graph.forward, 0, 0));
-            wkkt = transpose(&wkk);
-            wkk = wf_block(wkk, &wkkt);
-            dbg!("ok");
-            swaps[0]
-                .lock()
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .write_all(as_u8_slice(&wkk.0))
-                .unwrap();
+    let graph_checksum = manifest.graph;
+
+    let blocks = Blocks {
+        file: open_resumable(
+            &swap_name,
+            num_blocks * num_blocks * block_size * block_size,
+        )?,
+        block_size,
+        num_blocks,
+    };
+    manifest.verify(|b| blocks.read(b / num_blocks, b % num_blocks))?;
+    let stage = manifest.stage;
+    let progress = Progress::new(manifest, manifest_path, budget);
+    // processes block (i, j) of the current stage unless it was completed before
+    let process = |i: usize, j: usize, update: &dyn Fn() -> Result<Vec<u32>, io::Error>| {
+        if progress.is_done(i * num_blocks + j) {
+            return Ok(());
         }
+        progress.claim()?;
+        let block = update()?;
+        blocks.write(i, j, &block)?;
+        progress.complete(i * num_blocks + j, checksum(&block), &blocks.file)
+    };
 
-        (1..num_blocks).into_par_iter().for_each(|j| {
-            dbg!(j);
-            let mut wkj = Matrix(graph2matrix(&graph.forward, 0, j));
-            wkj = wf_block(wkj, &wkkt);
-            swaps[j]
-                .lock()
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .write_all(as_u8_slice(&wkj.0))
-                .unwrap();
-        });
-
-        (1..num_blocks).into_par_iter().for_each(|i| {
-            let mut buf = Vec::with_capacity(BLOCK_SIZE * BLOCK_SIZE);
-            unsafe { buf.set_len(BLOCK_SIZE * BLOCK_SIZE) }
-            dbg!(i);
-            let mut wik = Matrix(graph2matrix(&graph.forward, i, 0));
-            wik = wf_block(wik, &wkkt);
-            swaps[i * num_blocks]
-                .lock()
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .write_all(as_u8_slice(&wik.0))
-                .unwrap();
-
-            for j in 1..num_blocks {
-                swaps[j]
-                    .lock()
-                    .unwrap()
-                    .as_mut()
-                    .unwrap()
-                    .read_exact(as_u8_slice_mut(buf.as_mut_slice()))
-                    .unwrap();
-                let wkj = Matrix(buf);
-                let wikt = transpose(&wik);
-                let wij = wf_block(wkj, &wikt);
-                swaps[i * num_blocks + j]
-                    .lock()
-                    .unwrap()
-                    .as_mut()
-                    .unwrap()
-                    .write_all(as_u8_slice(&wij.0))
-                    .unwrap();
-                buf = wij.0;
-            }
+    if stage == 0 {
+        let cells: Vec<usize> = (0..num_blocks * num_blocks).collect();
+        let result = cells.into_par_iter().try_for_each(|b| {
+            let (i, j) = (b / num_blocks, b % num_blocks);
+            process(i, j, &|| Ok(graph2block(graph, i, j, block_size)))
         });
+        progress.interrupted(&blocks.file, result)?;
+        progress.save(&blocks.file, true)?;
     }
 
-    for k in 0..num_blocks {
-        dbg!(k);
-        let mut buf = Vec::with_capacity(BLOCK_SIZE * BLOCK_SIZE);
-        unsafe { buf.set_len(BLOCK_SIZE * BLOCK_SIZE) }
-        swaps[k * num_blocks + k]
-            .lock()
-            .unwrap()
-            .as_mut()
-            .unwrap()
-            .read_exact(as_u8_slice_mut(buf.as_mut_slice()))
-            .unwrap();
-        let mut wkk = Matrix(buf);
-        let ref wkkt = transpose(&wkk);
-        wkk = wf_block(wkk, wkkt);
-        swaps[0]
-            .lock()
-            .unwrap()
-            .as_mut()
-            .unwrap()
-            .write_all(as_u8_slice(&wkk.0))
-            .unwrap();
-
-        (1..num_blocks).into_par_iter().for_each(|j| {
-            let mut buf = Vec::with_capacity(BLOCK_SIZE * BLOCK_SIZE);
-            unsafe { buf.set_len(BLOCK_SIZE * BLOCK_SIZE) }
-            swaps[k * num_blocks + j]
-                .lock()
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .read_exact(as_u8_slice_mut(buf.as_mut_slice()))
-                .unwrap();
-            let mut wkj = Matrix(buf);
-            wkj = wf_block(wkj, wkkt);
-            swaps[j]
-                .lock()
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .write_all(as_u8_slice(&wkj.0))
-                .unwrap();
+    for k in usize::max(stage, 1) - 1..num_blocks {
+        // phase 1: the diagonal block
+        let result = process(k, k, &|| {
+            let mut wkk = blocks.read(k, k)?;
+            closure(&mut wkk, block_size);
+            Ok(wkk)
         });
-
-        (1..num_blocks).into_par_iter().for_each(|i| {
-            let mut buf = Vec::with_capacity(BLOCK_SIZE * BLOCK_SIZE);
-            unsafe { buf.set_len(BLOCK_SIZE * BLOCK_SIZE) }
-            let wik = Vec::with_capacity(BLOCK_SIZE * BLOCK_SIZE);
-            swaps[i * num_blocks + k]
-                .lock()
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .read_exact(as_u8_slice_mut(buf.as_mut_slice()))
-                .unwrap();
-            let mut wik = Matrix(wik);
-            wik = wf_block(wik, wkkt);
-            swaps[i * num_blocks]
-                .lock()
-                .unwrap()
-                .as_mut()
-                .unwrap()
-                .write_all(as_u8_slice(&wik.0))
-                .unwrap();
-
-            for j in 1..num_blocks {
-                swaps[j]
-                    .lock()
-                    .unwrap()
-                    .as_mut()
-                    .unwrap()
-                    .read_exact(as_u8_slice_mut(buf.as_mut_slice()))
-                    .unwrap();
-                let wkj = Matrix(buf);
-                let ref wikt = transpose(&wik);
-                let wij = wf_block(wkj, wikt);
-                swaps[i * num_blocks + j]
-                    .lock()
-                    .unwrap()
-                    .as_mut()
-                    .unwrap()
-                    .write_all(as_u8_slice(&wij.0))
-                    .unwrap();
-                buf = wij.0;
+        progress.interrupted(&blocks.file, result)?;
+        let wkk = blocks.read(k, k)?;
+
+        // phase 2: blocks in row and column k, paths leaving or entering block k
+        let others: Vec<usize> = (0..num_blocks).filter(|&j| j != k).collect();
+        let result = others.par_iter().try_for_each(|&j| {
+            process(k, j, &|| {
+                let wkj = blocks.read(k, j)?;
+                let mut relaxed = wkj.clone();
+                min_plus(&mut relaxed, &wkk, &wkj, block_size, block_size, block_size);
+                Ok(relaxed)
+            })?;
+            process(j, k, &|| {
+                let wjk = blocks.read(j, k)?;
+                let mut relaxed = wjk.clone();
+                min_plus(&mut relaxed, &wjk, &wkk, block_size, block_size, block_size);
+                Ok(relaxed)
+            })
+        });
+        progress.interrupted(&blocks.file, result)?;
+
+        // phase 3: all remaining blocks, paths passing block k
+        let result = others.par_iter().try_for_each(|&i| {
+            let wik = blocks.read(i, k)?;
+            for &j in others.iter() {
+                process(i, j, &|| {
+                    let mut wij = blocks.read(i, j)?;
+                    let wkj = blocks.read(k, j)?;
+                    min_plus(&mut wij, &wik, &wkj, block_size, block_size, block_size);
+                    Ok(wij)
+                })?;
             }
+            Ok::<(), io::Error>(())
         });
+        progress.interrupted(&blocks.file, result)?;
+        progress.save(&blocks.file, true)?;
     }
 
-    {
-        let mut wtr = BufWriter::new(File::create(&file_name)?);
-        let mut buf = Vec::with_capacity(BLOCK_SIZE);
-        unsafe { buf.set_len(BLOCK_SIZE * BLOCK_SIZE) }
-        for b_i in 0..num_blocks {
-            for r in 0..BLOCK_SIZE {
-                for b_j in 0..num_blocks {
-                    swaps[b_i * num_blocks + b_j]
-                        .lock()
-                        .unwrap()
-                        .as_mut()
-                        .unwrap()
-                        .read_exact(as_u8_slice_mut(buf.as_mut_slice()))
-                        .unwrap();
-                    let index = ((b_i * num_blocks + b_j) * BLOCK_SIZE + num_blocks * r)
-                        * BLOCK_SIZE
-                        * std::mem::size_of::<u32>();
-                    wtr.seek(SeekFrom::Start(index as u64)).unwrap();
-                    wtr.write_all(as_u8_slice(&buf)).unwrap();
-                    if r == (BLOCK_SIZE - 1) {
-                        *swaps[b_i * num_blocks + b_j].lock().unwrap() = None;
-                    }
-                }
-            }
+    let header = Header {
+        graph: graph_checksum,
+        ..Header::new(size, size)
+            .with_param("algorithm", "warshall_floyd")
+            .with_param("block_size", block_size)
+    };
+    let writer = CostMatrixWriter::create(&file_name, header)?;
+    let mut buf = vec![0; block_size];
+    let mut record = Vec::with_capacity(num_blocks * block_size);
+    for row in 0..size {
+        let (b_i, r) = (row / block_size, row % block_size);
+        record.clear();
+        for b_j in 0..num_blocks {
+            blocks.read_row(b_i, b_j, r, &mut buf)?;
+            record.extend_from_slice(&buf);
         }
+        record.truncate(size);
+        writer.write_row(row, &record)?;
     }
-    CostMatrix::new(file_name, size)
+    writer.sync()?;
+    progress.save(&blocks.file, true)?;
+    drop(blocks);
+    fs::remove_file(swap_name)?;
+    writer.finish()
 }
 
-/// Calculate all-pairs shortest paths using Dijkstra's algorithm.
+/// Calculate all-pairs shortest paths using Dijkstra's algorithm with the queue `Q`, running one
+/// search per row in parallel.
+///
+/// Completed rows are recorded with their checksums in a checkpoint manifest in `dir`. When
+/// called again with the same arguments, an interrupted run verifies the recorded rows and only
+/// computes the missing or partially written ones.
 ///
 /// # Arguments
 ///
 /// * `size` - Number of vertices.
 /// * `graph` - Graph represented as a neighbor list.
 /// * `dir` - Path to the directory for storing the result file.
-pub fn apsp(size: usize, graph: &NeighborList, dir: &Path) -> Result<CostMatrix, io::Error> {
+pub fn apsp<Q>(size: usize, graph: &NeighborList, dir: &Path) -> Result<CostMatrix, io::Error>
+where
+    Q: InitDijkstra + Send,
+    Q::Data<Hashed>: Send,
+{
+    apsp_with_sources::<Q>(size, graph, Sources::All, dir)
+}
+
+/// Calculate shortest paths from `sources` to all vertices like [`apsp`].
+///
+/// Only the rows of the sources are computed, rows completed by earlier runs in `dir` are kept,
+/// so the sources can be computed in several runs. The returned `CostMatrix` fails to get costs
+/// from rows that were not computed.
+pub fn apsp_with_sources<Q>(
+    size: usize,
+    graph: &NeighborList,
+    sources: impl Into<Sources>,
+    dir: &Path,
+) -> Result<CostMatrix, io::Error>
+where
+    Q: InitDijkstra + Send,
+    Q::Data<Hashed>: Send,
+{
+    apsp_with_budget::<Q>(size, graph, &sources.into(), dir, usize::MAX)
+}
+
+/// Runs [`apsp_with_sources`], interrupting it after `budget` rows.
+fn apsp_with_budget<Q>(
+    size: usize,
+    graph: &NeighborList,
+    sources: &Sources,
+    dir: &Path,
+    budget: usize,
+) -> Result<CostMatrix, io::Error>
+where
+    Q: InitDijkstra + Send,
+    Q::Data<Hashed>: Send,
+{
     if !dir.is_dir() {
         return Err(Error::new(
             io::ErrorKind::InvalidInput,
             "apsp expects a dir",
         ));
     }
-    let count = AtomicU32::from(0);
-    let current_local: DateTime<Local> = Local::now();
-    let timestamp = current_local.format("%Y%m%d%H%M%S");
+    let mut rows = sources.rows(size)?;
+    let manifest_path = dir.join(APSP_MANIFEST);
+    let mut manifest = resume(&manifest_path, "apsp", (size, 0), graph, size)?;
+    let file_name = dir.join(&manifest.output);
+    let mut writer = match CostMatrixWriter::open(&file_name) {
+        Ok(writer)
+            if writer.header().rows == size
+                && writer.header().cols == size
+                && matches!(writer.header().valid_rows(), Ok(Some(_))) =>
+        {
+            writer
+        }
+        // rows recorded for a missing or different file fail the verification
+        _ => {
+            let header = Header {
+                graph: manifest.graph,
+                ..Header::new(size, size)
+                    .with_param("algorithm", "apsp")
+                    .with_param("queue", std::any::type_name::<Q>())
+                    .with_valid_rows(&Bitmap::new(size))
+            };
+            CostMatrixWriter::create(&file_name, header)?
+        }
+    };
+    manifest.verify(|row| writer.read_row(row))?;
+    // rows that failed the verification must not be read from the file until they are redone
+    writer.write_valid_rows(&manifest.done)?;
+    rows.sort_unstable();
+    rows.dedup();
+    rows.retain(|&row| !manifest.done.get(row));
+    let total = rows.len();
+    let progress = Progress::new(manifest, manifest_path, budget);
 
-    let ref file_name = format!("{}/costmatrix_{}", dir.to_str().unwrap(), timestamp);
-    let file_name = Path::new(file_name);
-    {
-        let wtr = Mutex::new(BufWriter::new(File::create(&file_name)?));
-        let _ = (0..(size / NERF_FACTOR)).into_par_iter().for_each(|row| {
-            let source: OwnedLookup<PentaryHeap> =
-                OwnedLookup::from((row.try_into().unwrap(), size));
-            let result = sssp(source, &graph);
-            let record: Vec<u32> = (0..size)
-                .map(move |i| {
-                    let v: Vertex = i.try_into().unwrap();
-                    result.get_dist(v).unwrap()
-                })
-                .collect();
-
-            //lock
-            {
-                let mut lock = wtr.lock().unwrap();
-
-                let _ = lock
-                    .seek(SeekFrom::Start(
-                        (row * size * std::mem::size_of::<u32>()) as u64,
-                    ))
-                    .unwrap();
-                lock.write_all(as_u8_slice(&record)).unwrap();
-            }
-            //keep calm ☕
-            let status = count.fetch_add(1, Ordering::Relaxed) + 1;
-            if status % 100 == 0 {
-                let n: u32 = size.try_into().unwrap();
-                let ratio = f64::from(status) / f64::from(n / NERF_FACTOR as u32) * 100.;
-                print!("processed {:.3}%\r", ratio);
-                stdout().flush().unwrap();
-            }
-        });
-        CostMatrix::new(file_name, size)
-    }
+    let count = AtomicU32::from(0);
+    let result = rows.into_par_iter().try_for_each(|row| {
+        progress.claim()?;
+        let source = Q::Data::<Hashed>::from((row.try_into().unwrap(), size));
+        let result = sssp(source, graph);
+        let record: Vec<u32> = (0..size)
+            .map(move |i| {
+                let v: Vertex = i.try_into().unwrap();
+                result.get_dist(v).unwrap_or(u32::MAX)
+            })
+            .collect();
+        writer.write_row(row, &record)?;
+        progress.complete(row, checksum(&record), &writer)?;
+        //keep calm ☕
+        let status = count.fetch_add(1, Ordering::Relaxed) + 1;
+        if status % 100 == 0 {
+            let ratio = f64::from(status) / total as f64 * 100.;
+            print!("processed {:.3}%\r", ratio);
+            stdout().flush().unwrap();
+        }
+        Ok(())
+    });
+    progress.interrupted(&writer, result)?;
+    progress.save(&writer, false)?;
+    writer.write_valid_rows(&progress.into_manifest().done)?;
+    writer.finish()
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
+    use std::{io, path::Path};
 
     use crate::{
-        all_pairs::{apsp, warshall_floyd, NERF_FACTOR},
-        dijkstra::{DicirectionalList, NeighborList, StructuredEdges},
-        dimacs::{load_edges, load_max_vertex, Vertex},
+        all_pairs::{
+            apsp, apsp_with_budget, apsp_with_sources, warshall_floyd, warshall_floyd_with_budget,
+            Sources, APSP_MANIFEST, WARSHALL_FLOYD_MANIFEST,
+        },
+        checkpoint::Manifest,
+        dijkstra::{sssp, Dijkstra, Neighbor, NeighborList, OwnedLookup, StructuredEdges},
+        dimacs::{
+            load_edges, load_max_vertex, tests::random_edges, CostMatrix, CostMatrixWriter, Vertex,
+            UNDEFINED,
+        },
+        implicit_heaps::{BinaryHeap, PentaryHeap},
     };
 
     #[test]
@@ -393,34 +595,194 @@ mod tests {
         let size = n + 1;
         let edges = load_edges(Path::new(&format!("./data/{}-d.gr", region)));
         let graph: NeighborList = StructuredEdges::new(size, edges);
-        let ref dir = Path::new("./test");
-
-        let cost = apsp(size, &graph, dir).unwrap();
-
-        let n: u32 = size.try_into().unwrap();
-        dbg!(n);
-        for node in 1u32..(n / NERF_FACTOR as u32) {
+        // rows of earlier runs in the same directory would be resumed
+        let dir = tempfile::tempdir().unwrap();
+
+        // all rows take too long, so only some of them are computed
+        let rows: u32 = 100;
+        let sources = Vertex(1)..=Vertex(rows);
+        let cost = apsp_with_sources::<PentaryHeap>(size, &graph, sources, dir.path()).unwrap();
+        for node in 1u32..=rows {
             assert_eq!(cost.get(Vertex(node), Vertex(node)).unwrap(), 0);
         }
+        assert!(!cost.is_valid(Vertex(rows + 1)));
+        assert!(cost.get(Vertex(rows + 1), Vertex(1)).is_err());
     }
 
-    // disabled because it takes just too long
-    //#[test]
-    #[allow(dead_code)]
-    fn apsp_wf_test() {
-        let region = "NY";
-        let n: usize = load_max_vertex(Path::new(&format!("./data/{}.co", region))).into();
-        let size = n + 1;
-        let edges = load_edges(Path::new(&format!("./data/{}-d.gr", region)));
-        let graph: DicirectionalList<NeighborList> = DicirectionalList::new(size, edges);
-        let ref dir = Path::new("./test");
+    #[test]
+    fn warshall_floyd_matches_apsp() {
+        let n = 250;
+        let edges = random_edges(n, 3 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.into_iter());
+        let dir = tempfile::tempdir().unwrap();
+        let expected = apsp::<PentaryHeap>(n, &graph, dir.path()).unwrap();
+        let dists = distances(&graph, n);
+        // block sizes that divide the matrix, need padding and exceed it
+        for block_size in [25, 64, n, 300] {
+            let dir = tempfile::tempdir().unwrap();
+            let cost = warshall_floyd(n, &graph, block_size, dir.path()).unwrap();
+            for (i, row) in dists.iter().enumerate() {
+                let source = Vertex::try_from(i).unwrap();
+                for (j, &dist) in row.iter().enumerate() {
+                    let target = Vertex::try_from(j).unwrap();
+                    assert_eq!(cost.get(source, target).unwrap(), dist);
+                    assert_eq!(expected.get(source, target).unwrap(), dist);
+                }
+            }
+        }
+    }
 
-        let cost = warshall_floyd(size, &graph, dir).unwrap();
+    /// Calculates the distances from the first `rows` vertices with one search per row.
+    fn distances(graph: &NeighborList, rows: usize) -> Vec<Vec<u32>> {
+        let n = graph.len();
+        (0..rows)
+            .map(|i| {
+                let source = Vertex::try_from(i).unwrap();
+                let result = sssp(OwnedLookup::<BinaryHeap>::from((source, n)), graph);
+                (0..n)
+                    .map(|j| result.get_dist(Vertex::try_from(j).unwrap()).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
 
-        let n: u32 = size.try_into().unwrap();
-        dbg!(n);
-        for node in 1u32..(n / NERF_FACTOR as u32) {
-            assert_eq!(cost.get(Vertex(node), Vertex(node)).unwrap(), 0);
+    #[test]
+    fn apsp_resume() {
+        let n = 2000;
+        let edges = random_edges(n, 3 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.into_iter());
+        let dir = tempfile::tempdir().unwrap();
+        let sources = Sources::from(Vertex(1)..=Vertex(10));
+        let result = apsp_with_budget::<PentaryHeap>(n, &graph, &sources, dir.path(), 4);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::Interrupted);
+
+        let manifest_path = dir.path().join(APSP_MANIFEST);
+        let manifest = Manifest::load(&manifest_path).unwrap().unwrap();
+        assert_eq!(manifest.done.count(), 4);
+        // rows of an interrupted run are not valid before they are verified
+        let partial = CostMatrix::open(&dir.path().join(&manifest.output)).unwrap();
+        assert_eq!(partial.iter_rows().count(), 0);
+        drop(partial);
+        // a completed row that was only partially written
+        let row = (0..n).find(|&row| manifest.done.get(row)).unwrap();
+        let writer = CostMatrixWriter::open(&dir.path().join(&manifest.output)).unwrap();
+        writer.write_row(row, &vec![7; n]).unwrap();
+        drop(writer);
+
+        apsp_with_sources::<PentaryHeap>(n, &graph, sources, dir.path()).unwrap();
+        // rows of earlier runs stay valid
+        let more = vec![Vertex(20), Vertex(20)];
+        let cost = apsp_with_sources::<PentaryHeap>(n, &graph, more, dir.path()).unwrap();
+        let manifest = Manifest::load(&manifest_path).unwrap().unwrap();
+        assert_eq!(manifest.done.count(), 11);
+        assert!(cost.is_valid(Vertex(20)) && !cost.is_valid(Vertex(11)));
+        assert!(cost.get(Vertex(11), Vertex(1)).is_err());
+        for (i, row) in distances(&graph, 10).iter().enumerate() {
+            let source = Vertex::try_from(i).unwrap();
+            for (j, &dist) in row.iter().enumerate() {
+                let target = Vertex::try_from(j).unwrap();
+                assert_eq!(cost.get(source, target).unwrap(), dist);
+            }
+        }
+
+        // the computed rows are recorded in the file
+        let reopened = CostMatrix::open(&dir.path().join(&manifest.output)).unwrap();
+        let err = reopened.get(Vertex(11), Vertex(1)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(
+            reopened.iter_rows().collect::<Vec<_>>(),
+            cost.iter_rows().collect::<Vec<_>>()
+        );
+
+        // the file is not written while it is mapped
+        let sources = vec![Vertex(30)];
+        let err = apsp_with_sources::<PentaryHeap>(n, &graph, sources, dir.path())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(cost.get(Vertex(20), Vertex(20)).unwrap(), 0);
+        drop((cost, reopened));
+        let sources = vec![Vertex(30)];
+        let cost = apsp_with_sources::<PentaryHeap>(n, &graph, sources, dir.path()).unwrap();
+        assert!(cost.is_valid(Vertex(30)) && cost.is_valid(Vertex(20)));
+    }
+
+    #[test]
+    fn warshall_floyd_resume() {
+        let n = 100;
+        let edges = random_edges(n, 3 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.into_iter());
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join(WARSHALL_FLOYD_MANIFEST);
+        // 49 blocks are written initially and each round updates them again
+        for budget in [30, 60, 100] {
+            let result = warshall_floyd_with_budget(n, &graph, 16, dir.path(), budget);
+            assert_eq!(result.err().unwrap().kind(), io::ErrorKind::Interrupted);
+            let mut manifest = Manifest::load(&manifest_path).unwrap().unwrap();
+            assert!(manifest.done.count() > 0);
+            // the checksum of a completed block does not match as it was not fully written
+            let block = (0..49).find(|&b| manifest.done.get(b)).unwrap();
+            manifest.checksums[block] ^= 1;
+            manifest.save(&manifest_path).unwrap();
+        }
+        let cost = warshall_floyd(n, &graph, 16, dir.path()).unwrap();
+        let manifest = Manifest::load(&manifest_path).unwrap().unwrap();
+        assert_eq!(manifest.stage, 7 + 2);
+        assert!(!dir
+            .path()
+            .join(format!("{}.blocks", manifest.output))
+            .exists());
+        for (i, row) in distances(&graph, n).iter().enumerate() {
+            let source = Vertex::try_from(i).unwrap();
+            for (j, &dist) in row.iter().enumerate() {
+                let target = Vertex::try_from(j).unwrap();
+                assert_eq!(cost.get(source, target).unwrap(), dist);
+            }
+        }
+        // a completed run is not computed again
+        assert!(warshall_floyd_with_budget(n, &graph, 16, dir.path(), 0).is_ok());
+    }
+
+    #[test]
+    fn apsp_unreachable() {
+        let graph: NeighborList = vec![
+            vec![Neighbor {
+                to: Vertex(2),
+                weight: 3,
+            }],
+            Vec::new(),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let cost = apsp::<PentaryHeap>(2, &graph, dir.path()).unwrap();
+        assert_eq!(cost.get(Vertex(1), Vertex(2)).unwrap(), 3);
+        assert_eq!(cost.get(Vertex(2), Vertex(1)).unwrap(), u32::MAX);
+        for sources in [vec![Vertex(3)], vec![Vertex(0)]] {
+            assert!(apsp_with_sources::<PentaryHeap>(2, &graph, sources, dir.path()).is_err());
         }
+        // ranges fail before their rows are listed
+        for sources in [Vertex(1)..=UNDEFINED, Vertex(1)..=Vertex(u32::MAX)] {
+            assert!(apsp_with_sources::<PentaryHeap>(2, &graph, sources, dir.path()).is_err());
+        }
+    }
+
+    #[test]
+    fn warshall_floyd_unreachable() {
+        let graph: NeighborList = vec![
+            vec![Neighbor {
+                to: Vertex(2),
+                weight: 3,
+            }],
+            vec![Neighbor {
+                to: Vertex(3),
+                weight: 4,
+            }],
+            Vec::new(),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let cost = warshall_floyd(3, &graph, 2, dir.path()).unwrap();
+        assert_eq!(cost.get(Vertex(1), Vertex(3)).unwrap(), 7);
+        assert_eq!(cost.get(Vertex(2), Vertex(2)).unwrap(), 0);
+        assert_eq!(cost.get(Vertex(3), Vertex(1)).unwrap(), u32::MAX);
+        assert!(warshall_floyd(3, &graph, 0, dir.path()).is_err());
     }
 }
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+use nohash_hasher::NoHashHasher;
+
+use crate::dijkstra::{
+    assert_send, Backend, DecreaseKey, InitDijkstra, Item, OwnedLookup, PriorityQueue,
+};
+use crate::dimacs::Vertex;
+
+// Enough for the arc lengths of most road networks, the ring grows if a key does not fit.
+const DEFAULT_BUCKETS: usize = 1024;
+
+/// Represents Dial's bucket queue for monotone integer keys.
+///
+/// The buckets form a ring covering the keys from the last popped key onwards, one bucket per
+/// key. As long as the ring is longer than the largest arc weight every pushed key fits, otherwise
+/// the ring doubles. Pushed keys must not be smaller than the last popped key, which holds for
+/// Dijkstra.
+pub struct BucketQueue {
+    /// The bucket of a key is the key modulo the number of buckets, which is a power of two.
+    buckets: Vec<Vec<Item>>,
+    /// Position of every item as (bucket, index in bucket).
+    lookup: HashMap<Vertex, (usize, usize), BuildHasherDefault<NoHashHasher<u32>>>,
+    /// The smallest key that can be in the queue.
+    cursor: u32,
+    len: usize,
+}
+
+impl From<Vertex> for BucketQueue {
+    #[inline]
+    fn from(value: Vertex) -> Self {
+        let mut queue = Self {
+            buckets: vec![Vec::new(); DEFAULT_BUCKETS],
+            lookup: HashMap::default(),
+            cursor: 0,
+            len: 0,
+        };
+        queue.push(0, value);
+        queue
+    }
+}
+
+impl BucketQueue {
+    #[inline]
+    fn bucket(&self, key: u32) -> usize {
+        key as usize & (self.buckets.len() - 1)
+    }
+
+    #[inline]
+    fn insert(&mut self, item: Item) {
+        let bucket = self.bucket(item.key);
+        self.lookup
+            .insert(item.value, (bucket, self.buckets[bucket].len()));
+        self.buckets[bucket].push(item);
+    }
+
+    #[inline]
+    fn remove(&mut self, bucket: usize, index: usize) -> Item {
+        let item = self.buckets[bucket].swap_remove(index);
+        if let Some(moved) = self.buckets[bucket].get(index) {
+            self.lookup.insert(moved.value, (bucket, index));
+        }
+        item
+    }
+
+    /// Grows the ring until it covers `key`.
+    fn grow(&mut self, key: u32) {
+        let span = (key - self.cursor) as usize + 1;
+        let len = usize::max(span.next_power_of_two(), 2 * self.buckets.len());
+        let items: Vec<Item> = self.buckets.iter_mut().flat_map(|b| b.drain(..)).collect();
+        self.buckets.resize_with(len, Vec::new);
+        for item in items {
+            self.insert(item);
+        }
+    }
+}
+
+impl PriorityQueue for BucketQueue {
+    type RefType = Vertex;
+    type Key = u32;
+    type Value = Vertex;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut bucket = self.bucket(self.cursor);
+        while self.buckets[bucket].is_empty() {
+            self.cursor += 1;
+            bucket = self.bucket(self.cursor);
+        }
+        let min = self.buckets[bucket].pop()?;
+        self.lookup.remove(&min.value);
+        self.len -= 1;
+        Some((min.key, min.value))
+    }
+
+    #[inline]
+    fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
+        debug_assert!(key >= self.cursor, "keys have to be monotone");
+        if (key - self.cursor) as usize >= self.buckets.len() {
+            self.grow(key);
+        }
+        self.insert(Item { key, value });
+        self.len += 1;
+        value
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        for bucket in self.buckets.iter_mut() {
+            bucket.clear();
+        }
+        self.lookup.clear();
+        self.cursor = 0;
+        self.len = 0;
+        self.push(0, source);
+    }
+}
+
+impl DecreaseKey for BucketQueue {
+    #[inline]
+    fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
+        let (bucket, index) = self.lookup[&of];
+        let mut item = self.remove(bucket, index);
+        item.key = key;
+        self.insert(item);
+    }
+}
+
+impl InitDijkstra for BucketQueue {
+    type Data<B: Backend> = OwnedLookup<Self, B>;
+}
+
+const _: () = assert_send::<BucketQueue>();
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{Dijkstra, Neighbor};
+
+    #[test]
+    fn growing_ring() {
+        let mut queue = BucketQueue::from(Vertex(1));
+        queue.push(3, Vertex(2));
+        queue.push(5000, Vertex(3));
+        assert_eq!(queue.pop(), Some((0, Vertex(1))));
+        queue.push(DEFAULT_BUCKETS as u32 * 8, Vertex(4));
+        queue.decrease_key(Vertex(3), 2);
+        assert_eq!(queue.pop(), Some((2, Vertex(3))));
+        assert_eq!(queue.pop(), Some((3, Vertex(2))));
+        assert_eq!(queue.pop(), Some((DEFAULT_BUCKETS as u32 * 8, Vertex(4))));
+        assert_eq!(queue.pop(), None);
+        queue.reset(Vertex(9));
+        assert_eq!(queue.pop(), Some((0, Vertex(9))));
+    }
+
+    #[test]
+    fn push_pop_bucket_queue() {
+        let n = 10000;
+        let mut highest_min = 0;
+        let mut dijkstra: OwnedLookup<BucketQueue> = OwnedLookup::from((Vertex(1), n));
+        let mut rng = thread_rng();
+        //push
+        for i in 1..n {
+            let to = Vertex::try_from(i).unwrap();
+            dijkstra.explore(
+                Vertex(1),
+                0,
+                &Neighbor {
+                    weight: rng.gen_range(1..1000000),
+                    to,
+                },
+            );
+        }
+        //decrease_key
+        for _ in 0..n {
+            let to: Vertex = rng.gen_range(1..n).try_into().unwrap();
+            let (key, _) = dijkstra.meta.get(&to).unwrap();
+            let key = key / 2;
+            dijkstra.explore(Vertex(1), 0, &Neighbor { weight: key, to });
+        }
+        //pop
+        for _ in 0..n {
+            let (key, popped) = dijkstra.pop_min().unwrap();
+            let (stored_key, _) = dijkstra.meta.get(&popped).unwrap();
+            assert_eq!(key, *stored_key);
+            assert!(key >= highest_min);
+            highest_min = u32::max(highest_min, key);
+        }
+        assert_eq!(dijkstra.pop_min(), None);
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Error},
+    path::Path,
+};
+
+use crate::dijkstra::NeighborList;
+
+/// Offset basis of the 64-bit FNV-1a hash.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+/// Prime of the 64-bit FNV-1a hash.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[inline]
+fn fnv(hash: u64, value: u32) -> u64 {
+    value.to_le_bytes().iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Calculates the checksum of a row or block as it is stored on disk.
+pub fn checksum(values: &[u32]) -> u64 {
+    values
+        .iter()
+        .fold(FNV_OFFSET, |hash, &value| fnv(hash, value))
+}
+
+/// Calculates a fingerprint of the graph, so a checkpoint is not resumed with a different one.
+pub fn fingerprint(graph: &NeighborList) -> u64 {
+    let mut hash = fnv(FNV_OFFSET, graph.len() as u32);
+    for neighbors in graph {
+        hash = fnv(hash, neighbors.len() as u32);
+        for neighbor in neighbors {
+            hash = fnv(fnv(hash, neighbor.to.0), neighbor.weight);
+        }
+    }
+    hash
+}
+
+/// Represents a set of completed items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitmap {
+    /// Constructs an empty `Bitmap` for `len` items.
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, i: usize, value: bool) {
+        if value {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    /// Returns the number of completed items.
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// Encodes the items as hex words of fixed width, so the encoding of a `Bitmap` has the
+    /// same length whichever items are completed.
+    pub fn to_hex(&self) -> String {
+        let mut text = String::with_capacity(self.words.len() * 16);
+        for word in self.words.iter() {
+            write!(text, "{:016x}", word).unwrap();
+        }
+        text
+    }
+
+    /// Decodes a `Bitmap` of `len` items encoded by [`Bitmap::to_hex`], `None` if `text` is no
+    /// such encoding.
+    pub fn from_hex(len: usize, text: &str) -> Option<Self> {
+        let mut bitmap = Self::new(len);
+        if !text.is_ascii() || text.len() != bitmap.words.len() * 16 {
+            return None;
+        }
+        for (word, hex) in bitmap.words.iter_mut().zip(text.as_bytes().chunks(16)) {
+            *word = u64::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+        }
+        // items past the end must not be set
+        if !len.is_multiple_of(64) && bitmap.words.last()? >> (len % 64) != 0 {
+            return None;
+        }
+        Some(bitmap)
+    }
+}
+
+/// Represents the checkpoint of an all-pairs computation, stored as a manifest next to its
+/// output so an interrupted run can be resumed.
+///
+/// Work is split into items, the rows of `apsp` or the blocks of one stage of
+/// `warshall_floyd`. Each completed item is marked in `done` together with the checksum of its
+/// data, which detects items that were only partially written before the interruption.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// Name of the algorithm that wrote the checkpoint.
+    pub algorithm: String,
+    /// Number of vertices.
+    pub size: usize,
+    /// Size of the blocks, 0 if the computation is not blocked.
+    pub block_size: usize,
+    /// Fingerprint of the input graph.
+    pub graph: u64,
+    /// File name of the cost matrix inside the output directory.
+    pub output: String,
+    /// Index of the current stage of a computation running in several stages.
+    pub stage: usize,
+    /// Completed items of the current stage.
+    pub done: Bitmap,
+    /// Checksums of the completed items.
+    pub checksums: Vec<u64>,
+}
+
+impl Manifest {
+    /// Constructs a new `Manifest` of a computation that has not started yet.
+    pub fn new(
+        algorithm: &str,
+        size: usize,
+        block_size: usize,
+        graph: u64,
+        output: String,
+        items: usize,
+    ) -> Self {
+        Self {
+            algorithm: algorithm.to_owned(),
+            size,
+            block_size,
+            graph,
+            output,
+            stage: 0,
+            done: Bitmap::new(items),
+            checksums: vec![0; items],
+        }
+    }
+
+    /// Returns whether the checkpoint belongs to the same computation of `items` items per
+    /// stage. A manifest recording another number of items is damaged and cannot be resumed.
+    pub fn matches(
+        &self,
+        algorithm: &str,
+        size: usize,
+        block_size: usize,
+        graph: u64,
+        items: usize,
+    ) -> bool {
+        self.algorithm == algorithm
+            && self.size == size
+            && self.block_size == block_size
+            && self.graph == graph
+            && self.done.len() == items
+            && self.checksums.len() == items
+    }
+
+    /// Marks item `i` as completed with the checksum of its data.
+    #[inline]
+    pub fn complete(&mut self, i: usize, checksum: u64) {
+        self.done.set(i, true);
+        self.checksums[i] = checksum;
+    }
+
+    /// Moves on to the next stage, which has no completed items yet.
+    pub fn advance(&mut self) {
+        self.stage += 1;
+        self.done.clear();
+        self.checksums.fill(0);
+    }
+
+    /// Verifies the completed items against their data read by `read` and marks items whose
+    /// checksum does not match as not completed. Returns the number of such items.
+    pub fn verify<F>(&mut self, read: F) -> Result<usize, io::Error>
+    where
+        F: Fn(usize) -> Result<Vec<u32>, io::Error>,
+    {
+        let mut invalid = 0;
+        for i in 0..self.done.len() {
+            if self.done.get(i) && checksum(&read(i)?) != self.checksums[i] {
+                self.done.set(i, false);
+                self.checksums[i] = 0;
+                invalid += 1;
+            }
+        }
+        Ok(invalid)
+    }
+
+    /// Loads the manifest at `path`, `None` if there is none.
+    pub fn load(path: &Path) -> Result<Option<Self>, io::Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid = |field: &str| {
+            Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid checkpoint manifest: bad {}", field),
+            )
+        };
+        let mut fields = std::collections::HashMap::new();
+        for line in text.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            fields.insert(key, value);
+        }
+        let field = |key: &str| fields.get(key).copied().ok_or_else(|| invalid(key));
+        let number = |key: &str| field(key)?.parse::<usize>().map_err(|_| invalid(key));
+        let hex = |key: &str, value: &str| u64::from_str_radix(value, 16).map_err(|_| invalid(key));
+
+        let checksums = field("checksums")?
+            .split_whitespace()
+            .map(|value| hex("checksums", value))
+            .collect::<Result<Vec<u64>, io::Error>>()?;
+        let mut done = Bitmap::new(checksums.len());
+        for (i, word) in field("done")?.split_whitespace().enumerate() {
+            if i >= done.words.len() {
+                return Err(invalid("done"));
+            }
+            done.words[i] = hex("done", word)?;
+        }
+        Ok(Some(Self {
+            algorithm: field("algorithm")?.to_owned(),
+            size: number("size")?,
+            block_size: number("block_size")?,
+            graph: hex("graph", field("graph")?)?,
+            output: field("output")?.to_owned(),
+            stage: number("stage")?,
+            done,
+            checksums,
+        }))
+    }
+
+    /// Saves the manifest to `path`. It is written to a temporary file first and renamed, so
+    /// an interruption leaves either the old or the new manifest.
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        let mut text = String::new();
+        writeln!(text, "algorithm {}", self.algorithm).unwrap();
+        writeln!(text, "size {}", self.size).unwrap();
+        writeln!(text, "block_size {}", self.block_size).unwrap();
+        writeln!(text, "graph {:x}", self.graph).unwrap();
+        writeln!(text, "output {}", self.output).unwrap();
+        writeln!(text, "stage {}", self.stage).unwrap();
+        text.push_str("done");
+        for word in self.done.words.iter() {
+            write!(text, " {:x}", word).unwrap();
+        }
+        text.push_str("\nchecksums");
+        for checksum in self.checksums.iter() {
+            write!(text, " {:x}", checksum).unwrap();
+        }
+        text.push('\n');
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.manifest");
+        assert_eq!(Manifest::load(&path).unwrap(), None);
+
+        let mut manifest = Manifest::new("apsp", 100, 0, 42, "costmatrix".to_owned(), 100);
+        manifest.complete(3, checksum(&[1, 2, 3]));
+        manifest.complete(70, 7);
+        manifest.save(&path).unwrap();
+        let mut loaded = Manifest::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(loaded.done.count(), 2);
+        assert!(loaded.matches("apsp", 100, 0, 42, 100));
+        assert!(!loaded.matches("apsp", 100, 0, 43, 100));
+
+        // item 70 was not written as recorded
+        let invalid = loaded.verify(|i| Ok(if i == 3 { vec![1, 2, 3] } else { vec![0] }));
+        assert_eq!(invalid.unwrap(), 1);
+        assert!(loaded.done.get(3) && !loaded.done.get(70));
+
+        loaded.advance();
+        assert_eq!((loaded.stage, loaded.done.count()), (1, 0));
+
+        // a manifest cut off within the checksums records fewer items
+        let text = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &text[..text.len() - 10]).unwrap();
+        let truncated = Manifest::load(&path).unwrap().unwrap();
+        assert!(!truncated.matches("apsp", 100, 0, 42, 100));
+
+        fs::write(&path, "algorithm apsp\nsize x\n").unwrap();
+        assert!(Manifest::load(&path).is_err());
+    }
+
+    #[test]
+    fn bitmap_hex() {
+        let mut bitmap = Bitmap::new(70);
+        let empty = bitmap.to_hex();
+        bitmap.set(0, true);
+        bitmap.set(69, true);
+        let hex = bitmap.to_hex();
+        assert_eq!(hex.len(), empty.len());
+        assert_eq!(Bitmap::from_hex(70, &hex), Some(bitmap));
+        assert_eq!(Bitmap::from_hex(70, &empty), Some(Bitmap::new(70)));
+        assert_eq!(Bitmap::from_hex(69, &hex), None);
+        assert_eq!(Bitmap::from_hex(140, &hex), None);
+        assert_eq!(Bitmap::from_hex(70, &hex.replace('1', "x")), None);
+    }
+}
@@ -0,0 +1,265 @@
+use std::path::Path;
+
+use crate::dijkstra::{DicirectionalList, Graph, NeighborList, StructuredEdges};
+use crate::dimacs::{load_edges, Vertex};
+use crate::renumber::Renumbering;
+
+/// Represents a partition of the vertices of a graph into strongly connected components.
+pub struct Components {
+    /// Number of components.
+    pub count: usize,
+    /// Component of every vertex.
+    pub component: Vec<usize>,
+}
+
+impl Components {
+    /// Gets the size of every component.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.count];
+        for &c in self.component.iter() {
+            sizes[c] += 1;
+        }
+        sizes
+    }
+
+    /// Gets the vertices of the largest component in ascending order.
+    pub fn largest(&self) -> Vec<Vertex> {
+        let sizes = self.sizes();
+        let Some(largest) = (0..self.count).max_by_key(|&c| sizes[c]) else {
+            return Vec::new();
+        };
+        (0..self.component.len())
+            .filter(|&i| self.component[i] == largest)
+            .map(|i| Vertex::try_from(i).unwrap())
+            .collect()
+    }
+}
+
+/// Computes the strongly connected components with Tarjan's algorithm.
+pub fn tarjan<G: Graph>(graph: &G) -> Components {
+    let n = graph.num_vertices();
+    let mut index = vec![usize::MAX; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut component = vec![usize::MAX; n];
+    let mut count = 0;
+    let mut next = 0;
+
+    for root in 0..n {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        // depth first search with explicit call frames of a vertex and its unvisited arcs
+        let mut frames = vec![(root, graph.get_neighbors(Vertex::try_from(root).unwrap()))];
+        index[root] = next;
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        while let Some((u, neighbors)) = frames.last_mut() {
+            let u = *u;
+            if let Some(e) = neighbors.next() {
+                let v = usize::from(e.to);
+                if index[v] == usize::MAX {
+                    index[v] = next;
+                    low[v] = next;
+                    next += 1;
+                    stack.push(v);
+                    on_stack[v] = true;
+                    frames.push((v, graph.get_neighbors(e.to)));
+                } else if on_stack[v] {
+                    low[u] = usize::min(low[u], index[v]);
+                }
+                continue;
+            }
+            frames.pop();
+            if let Some((parent, _)) = frames.last() {
+                low[*parent] = usize::min(low[*parent], low[u]);
+            }
+            if low[u] == index[u] {
+                while let Some(v) = stack.pop() {
+                    on_stack[v] = false;
+                    component[v] = count;
+                    if v == u {
+                        break;
+                    }
+                }
+                count += 1;
+            }
+        }
+    }
+    Components { count, component }
+}
+
+/// Computes the strongly connected components with Kosaraju's algorithm, which uses the
+/// backward graph instead of transposing the forward graph.
+pub fn kosaraju<G: Graph>(graph: &DicirectionalList<G>) -> Components {
+    let n = graph.forward.num_vertices();
+    // order vertices by the time their forward search finishes
+    let mut visited = vec![false; n];
+    let mut finished = Vec::with_capacity(n);
+    for root in 0..n {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut frames = vec![(
+            root,
+            graph.forward.get_neighbors(Vertex::try_from(root).unwrap()),
+        )];
+        while let Some((u, neighbors)) = frames.last_mut() {
+            let u = *u;
+            if let Some(e) = neighbors.next() {
+                let v = usize::from(e.to);
+                if !visited[v] {
+                    visited[v] = true;
+                    frames.push((v, graph.forward.get_neighbors(e.to)));
+                }
+                continue;
+            }
+            frames.pop();
+            finished.push(u);
+        }
+    }
+    // collect components backwards in reverse finishing order
+    let mut component = vec![usize::MAX; n];
+    let mut count = 0;
+    let mut stack = Vec::new();
+    for &root in finished.iter().rev() {
+        if component[root] != usize::MAX {
+            continue;
+        }
+        component[root] = count;
+        stack.push(root);
+        while let Some(u) = stack.pop() {
+            for e in graph.backward.get_neighbors(Vertex::try_from(u).unwrap()) {
+                let v = usize::from(e.to);
+                if component[v] == usize::MAX {
+                    component[v] = count;
+                    stack.push(v);
+                }
+            }
+        }
+        count += 1;
+    }
+    Components { count, component }
+}
+
+/// Extracts the largest strongly connected component as a renumbered graph.
+/// Distances between its vertices are the same as in the original graph.
+pub fn largest_component<G: Graph>(graph: &G) -> (NeighborList, Renumbering) {
+    let map = Renumbering::from_order(tarjan(graph).largest(), graph.num_vertices());
+    (map.apply(graph), map)
+}
+
+/// Extracts the largest strongly connected component of both directions as a renumbered graph.
+pub fn largest_component_bidirectional<G: Graph>(
+    graph: &DicirectionalList<G>,
+) -> (DicirectionalList<NeighborList>, Renumbering) {
+    let map = Renumbering::from_order(kosaraju(graph).largest(), graph.forward.num_vertices());
+    (map.apply_bidirectional(graph), map)
+}
+
+/// Loads a graph of `size` vertices from a file downloaded from
+/// https://www.diag.uniroma1.it/challenge9/download.shtml.
+///
+/// # Arguments
+///
+/// * `largest_scc` - Restrict the graph to its largest strongly connected component, so every
+///   vertex is reachable from every other. Otherwise the mapping is the identity.
+pub fn load_graph(path: &Path, size: usize, largest_scc: bool) -> (NeighborList, Renumbering) {
+    let graph: NeighborList = StructuredEdges::new(size, load_edges(path));
+    if largest_scc {
+        largest_component(&graph)
+    } else {
+        (graph, Renumbering::identity(size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{sssp, Dijkstra, OwnedLookup};
+    use crate::dimacs::Edge;
+    use crate::implicit_heaps::BinaryHeap;
+
+    fn random_sparse_edges(n: usize, m: usize) -> Vec<Edge> {
+        let mut rng = thread_rng();
+        (0..m)
+            .map(|_| Edge {
+                from: Vertex::try_from(rng.gen_range(0..n)).unwrap(),
+                to: Vertex::try_from(rng.gen_range(0..n)).unwrap(),
+                weight: rng.gen_range(1..100),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small_components() {
+        // {1, 2, 3} is a cycle, 4 hangs off it and {5, 6} is a separate cycle
+        let edges = [(1, 2), (2, 3), (3, 1), (3, 4), (5, 6), (6, 5), (4, 5)].map(|(f, t)| Edge {
+            from: Vertex(f),
+            to: Vertex(t),
+            weight: 1,
+        });
+        let graph: NeighborList = StructuredEdges::new(6, edges.clone().into_iter());
+        let components = tarjan(&graph);
+        assert_eq!(components.count, 3);
+        assert_eq!(components.largest(), vec![Vertex(1), Vertex(2), Vertex(3)]);
+        let c = &components.component;
+        assert_eq!((c[0], c[1]), (c[2], c[2]));
+        assert_eq!(c[4], c[5]);
+        assert_ne!(c[3], c[0]);
+        assert_ne!(c[3], c[4]);
+    }
+
+    #[test]
+    fn tarjan_matches_kosaraju() {
+        let n = 1000;
+        for m in [n / 2, n, 2 * n] {
+            let edges = random_sparse_edges(n, m);
+            let graph: NeighborList = StructuredEdges::new(n, edges.clone().into_iter());
+            let bigraph: DicirectionalList<NeighborList> =
+                DicirectionalList::new(n, edges.into_iter());
+            let a = tarjan(&graph);
+            let b = kosaraju(&bigraph);
+            assert_eq!(a.count, b.count);
+            // both partitions agree if their ids correspond one to one
+            let mut translate = vec![usize::MAX; a.count];
+            for i in 0..n {
+                let (x, y) = (a.component[i], b.component[i]);
+                if translate[x] == usize::MAX {
+                    translate[x] = y;
+                }
+                assert_eq!(translate[x], y);
+            }
+        }
+    }
+
+    #[test]
+    fn largest_keeps_distances() {
+        let n = 1000;
+        let edges = random_sparse_edges(n, 2 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.clone().into_iter());
+        let (sub, map) = largest_component(&graph);
+        let bigraph: DicirectionalList<NeighborList> = DicirectionalList::new(n, edges.into_iter());
+        let (sub_bi, map_bi) = largest_component_bidirectional(&bigraph);
+        assert_eq!(map.num_vertices(), map_bi.num_vertices());
+        assert_eq!(sub_bi.forward.len(), sub.len());
+
+        let source = map.to_original(Vertex(1));
+        let full = sssp(OwnedLookup::<BinaryHeap>::from((source, n)), &graph);
+        let size = map.num_vertices();
+        let restricted = sssp(OwnedLookup::<BinaryHeap>::from((Vertex(1), size)), &sub);
+        for i in 0..size {
+            let v = Vertex::try_from(i).unwrap();
+            let dist = restricted
+                .get_dist(v)
+                .expect("vertices of a component reach each other");
+            assert_eq!(Some(dist), full.get_dist(map.to_original(v)));
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    os::unix::fs::FileExt,
+    path::Path,
+};
+
+use memmap2::Mmap;
+
+use crate::checkpoint::Bitmap;
+use crate::dimacs::{
+    invalid_data, lock, CostMatrix, CostMatrixWriter, ElementType, Header, Vertex, UNDEFINED,
+    VALID_ROWS_PARAM,
+};
+
+/// Default number of entries of a row that are compressed together.
+pub const DEFAULT_BLOCK_LEN: usize = 64;
+/// Header parameter holding the number of entries per block.
+const BLOCK_LEN_PARAM: &str = "block_len";
+/// Size of an entry of the row index.
+const INDEX_ENTRY: usize = std::mem::size_of::<u64>();
+/// Size of an entry of the block table of a row.
+const TABLE_ENTRY: usize = std::mem::size_of::<u32>();
+
+#[inline]
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+#[inline]
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, io::Error> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or_else(|| invalid_data("compressed block is truncated"))?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("compressed block has an overlong varint"))
+}
+
+/// Encodes `values` as zigzag coded differences to their predecessors, the first one to 0.
+fn encode_block(bytes: &mut Vec<u8>, values: &[u32]) {
+    let mut prev = 0i64;
+    for &value in values {
+        let delta = i64::from(value) - prev;
+        write_varint(bytes, ((delta << 1) ^ (delta >> 63)) as u64);
+        prev = i64::from(value);
+    }
+}
+
+/// Decodes the block at `pos` of `bytes` up to its entry `last`, which is returned.
+#[inline]
+fn decode_block<F>(bytes: &[u8], mut pos: usize, last: usize, mut f: F) -> Result<u32, io::Error>
+where
+    F: FnMut(u32),
+{
+    let mut value = 0u32;
+    for _ in 0..=last {
+        let zigzag = read_varint(bytes, &mut pos)?;
+        let delta = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+        value = u32::try_from(i64::from(value) + delta)
+            .map_err(|_| invalid_data("compressed block has an invalid value"))?;
+        f(value);
+    }
+    Ok(value)
+}
+
+/// Returns the byte offset of the rows of a compressed matrix, after its header and row index.
+#[inline]
+fn data_offset(header: &Header) -> Result<usize, io::Error> {
+    header
+        .rows
+        .checked_add(1)
+        .and_then(|entries| entries.checked_mul(INDEX_ENTRY))
+        .and_then(|index| index.checked_add(header.len()))
+        .ok_or_else(|| invalid_data("cost matrix is too large"))
+}
+
+/// Represents a compressed cost matrix, mapped into memory.
+///
+/// The file starts with a [`Header`] of element type [`ElementType::Varint`], followed by a row
+/// index of `rows + 1` little-endian `u64` offsets, where the data of row `i` spans from entry
+/// `i` to entry `i + 1`. Rows that were not computed are empty. Each row is split into blocks
+/// of `block_len` entries, located by a table of `u32` offsets at the start of the row. A block
+/// stores its entries as zigzag coded varint differences to the previous entry, so
+/// [`CompressedCostMatrix::get`] decodes at most `block_len` entries and infinity costs a single
+/// byte in unreachable regions.
+///
+/// The file holds a shared lock while it is mapped, like [`CostMatrix`].
+pub struct CompressedCostMatrix {
+    map: Mmap,
+    /// File of the map, holding its lock.
+    _file: File,
+    header: Header,
+    block_len: usize,
+    /// Byte offset of the row index in the file.
+    index: usize,
+    /// Byte offset of the rows in the file.
+    data: usize,
+}
+
+impl CompressedCostMatrix {
+    /// Opens a compressed cost matrix file.
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+        let header = Header::read(&file)?;
+        if header.element != ElementType::Varint {
+            return Err(invalid_data("cost matrix is not compressed"));
+        }
+        let block_len = header
+            .param(BLOCK_LEN_PARAM)
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|&block_len| block_len > 0)
+            .ok_or_else(|| invalid_data("compressed cost matrix has no valid block length"))?;
+        lock(&file, true)?;
+        // SAFETY: writers of this crate fail to lock the file until the map is dropped, other
+        // programs must not change it, the contract of the type
+        let map = unsafe { Mmap::map(&file)? };
+        let (index, data) = (header.len(), data_offset(&header)?);
+        let matrix = Self {
+            map,
+            _file: file,
+            header,
+            block_len,
+            index,
+            data,
+        };
+        if matrix.map.len() < data
+            || matrix.map.len() - data < matrix.offset(matrix.header.rows) as usize
+        {
+            return Err(invalid_data("cost matrix file is truncated"));
+        }
+        Ok(matrix)
+    }
+
+    /// Compresses `matrix` into a file at `path` with blocks of `block_len` entries. Rows that
+    /// were not computed are left empty.
+    pub fn compress(matrix: &CostMatrix, path: &Path, block_len: usize) -> Result<Self, io::Error> {
+        if block_len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "block length has to be positive",
+            ));
+        }
+        let mut header = matrix
+            .header()
+            .cloned()
+            .unwrap_or_else(|| Header::new(matrix.rows(), matrix.cols()));
+        header.element = ElementType::Varint;
+        // rows that were not computed are stored empty
+        header
+            .params
+            .retain(|(key, _)| key != BLOCK_LEN_PARAM && key != VALID_ROWS_PARAM);
+        let header = header.with_param(BLOCK_LEN_PARAM, block_len);
+        let data = data_offset(&header)?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        // truncating a mapped file would take the memory of the map away
+        lock(&file, false)?;
+        file.set_len(0)?;
+        file.write_all_at(&header.to_bytes()?, 0)?;
+        let mut writer = BufWriter::new(&file);
+        writer.seek(SeekFrom::Start(data as u64))?;
+
+        let blocks = header.cols.div_ceil(block_len);
+        let mut offsets = Vec::with_capacity(header.rows + 1);
+        let mut offset = 0u64;
+        let mut bytes = Vec::new();
+        offsets.push(offset);
+        for row in 0..header.rows {
+            let source = Vertex::try_from(row).unwrap();
+            if matrix.is_valid(source) {
+                bytes.clear();
+                bytes.resize(blocks * TABLE_ENTRY, 0);
+                for (block, values) in matrix.row(source).chunks(block_len).enumerate() {
+                    let start =
+                        u32::try_from(bytes.len() - blocks * TABLE_ENTRY).map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "compressed row exceeds the 4 GiB addressed by its block table",
+                            )
+                        })?;
+                    bytes[block * TABLE_ENTRY..(block + 1) * TABLE_ENTRY]
+                        .copy_from_slice(&start.to_le_bytes());
+                    encode_block(&mut bytes, values);
+                }
+                writer.write_all(&bytes)?;
+                offset += bytes.len() as u64;
+            }
+            offsets.push(offset);
+        }
+        writer.flush()?;
+        drop(writer);
+
+        let index: Vec<u8> = offsets
+            .iter()
+            .flat_map(|offset| offset.to_le_bytes())
+            .collect();
+        file.write_all_at(&index, header.len() as u64)?;
+        file.sync_data()?;
+        drop(file);
+        Self::open(path)
+    }
+
+    /// Decompresses the matrix into a raw cost matrix file at `path`.
+    pub fn decompress(&self, path: &Path) -> Result<CostMatrix, io::Error> {
+        let mut header = self.header.clone();
+        header.element = ElementType::U32;
+        header.params.retain(|(key, _)| key != BLOCK_LEN_PARAM);
+        let mut valid = Bitmap::new(self.rows());
+        for row in 0..self.rows() {
+            valid.set(row, self.is_valid(Vertex::try_from(row).unwrap()));
+        }
+        if valid.count() < self.rows() {
+            header = header.with_valid_rows(&valid);
+        }
+        let writer = CostMatrixWriter::create(path, header)?;
+        for row in 0..self.rows() {
+            if valid.get(row) {
+                let source = Vertex::try_from(row).unwrap();
+                writer.write_row(row, &self.row(source)?)?;
+            }
+        }
+        writer.finish()
+    }
+
+    #[inline]
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the number of entries per block.
+    #[inline]
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Returns the number of rows.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.header.rows
+    }
+
+    /// Returns the number of columns.
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.header.cols
+    }
+
+    /// Returns the entry `row` of the row index.
+    #[inline]
+    fn offset(&self, row: usize) -> u64 {
+        let at = self.index + row * INDEX_ENTRY;
+        u64::from_le_bytes(self.map[at..at + INDEX_ENTRY].try_into().unwrap())
+    }
+
+    /// Returns the compressed data of `row`, empty if it was not computed.
+    #[inline]
+    fn row_bytes(&self, row: usize) -> Result<&[u8], io::Error> {
+        let (start, end) = (self.offset(row), self.offset(row + 1));
+        if start > end || end > (self.map.len() - self.data) as u64 {
+            return Err(invalid_data(
+                "compressed cost matrix has an invalid row index",
+            ));
+        }
+        Ok(&self.map[self.data + start as usize..self.data + end as usize])
+    }
+
+    /// Returns whether the row of `source` was computed.
+    #[inline]
+    pub fn is_valid(&self, source: Vertex) -> bool {
+        source != UNDEFINED
+            && usize::from(source) < self.rows()
+            && self.offset(usize::from(source)) != self.offset(usize::from(source) + 1)
+    }
+
+    #[inline]
+    fn check_row(&self, source: Vertex) -> Result<&[u8], io::Error> {
+        if !self.is_valid(source) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("row of vertex {} was not computed", source.0),
+            ));
+        }
+        self.row_bytes(usize::from(source))
+    }
+
+    #[inline]
+    fn check_col(&self, target: Vertex) -> Result<usize, io::Error> {
+        if target == UNDEFINED || usize::from(target) >= self.cols() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("vertex {} is not a column of the matrix", target.0),
+            ));
+        }
+        Ok(usize::from(target))
+    }
+
+    /// Returns the position of `block` in the compressed data `bytes` of a row.
+    #[inline]
+    fn block_start(&self, bytes: &[u8], block: usize) -> Result<usize, io::Error> {
+        let blocks = self.cols().div_ceil(self.block_len);
+        let at = block * TABLE_ENTRY;
+        let offset = bytes
+            .get(at..at + TABLE_ENTRY)
+            .ok_or_else(|| invalid_data("compressed row is truncated"))?;
+        Ok(blocks * TABLE_ENTRY + u32::from_le_bytes(offset.try_into().unwrap()) as usize)
+    }
+
+    /// Gets the cost between two vertices, `u32::MAX` if `target` is unreachable. Decodes a
+    /// single block. Fails if the row of `source` was not computed.
+    pub fn get(&self, source: Vertex, target: Vertex) -> Result<u32, io::Error> {
+        let (bytes, col) = (self.check_row(source)?, self.check_col(target)?);
+        let start = self.block_start(bytes, col / self.block_len)?;
+        decode_block(bytes, start, col % self.block_len, |_| ())
+    }
+
+    /// Returns the costs from `source` to every vertex. Fails if the row of `source` was not
+    /// computed.
+    pub fn row(&self, source: Vertex) -> Result<Vec<u32>, io::Error> {
+        let bytes = self.check_row(source)?;
+        let mut costs = Vec::with_capacity(self.cols());
+        for block in 0..self.cols().div_ceil(self.block_len) {
+            let len = usize::min(self.block_len, self.cols() - block * self.block_len);
+            let start = self.block_start(bytes, block)?;
+            decode_block(bytes, start, len - 1, |cost| costs.push(cost))?;
+        }
+        Ok(costs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    #[test]
+    fn compress_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let (rows, cols) = (20, 150);
+        let mut rng = thread_rng();
+        let raw = dir.path().join("matrix.cost");
+        let mut writer = CostMatrixWriter::create(
+            &raw,
+            Header::new(rows, cols)
+                .with_param("algorithm", "test")
+                .with_valid_rows(&Bitmap::new(rows)),
+        )
+        .unwrap();
+        // costs grow along the columns with unreachable runs and single extreme entries
+        let mut expected = Vec::new();
+        for row in 0..rows {
+            let mut cost = rng.gen_range(0..1000);
+            let values: Vec<u32> = (0..cols)
+                .map(|col| match (col / 30 + row) % 5 {
+                    0 => u32::MAX,
+                    _ if rng.gen_range(0..20) == 0 => rng.gen(),
+                    _ => {
+                        cost += rng.gen_range(0..100);
+                        cost
+                    }
+                })
+                .collect();
+            writer.write_row(row, &values).unwrap();
+            expected.push(values);
+        }
+        let mut valid = Bitmap::new(rows);
+        (0..rows)
+            .filter(|row| row % 7 != 3)
+            .for_each(|row| valid.set(row, true));
+        writer.write_valid_rows(&valid).unwrap();
+        let matrix = writer.finish().unwrap();
+        let raw_len = std::fs::metadata(&raw).unwrap().len();
+
+        for block_len in [1, 7, DEFAULT_BLOCK_LEN, 1000] {
+            let path = dir.path().join(format!("matrix_{block_len}.cost"));
+            let compressed = CompressedCostMatrix::compress(&matrix, &path, block_len).unwrap();
+            assert_eq!(compressed.block_len(), block_len);
+            assert_eq!(compressed.header().param("algorithm"), Some("test"));
+            if block_len > 1 {
+                assert!(std::fs::metadata(&path).unwrap().len() < raw_len);
+            }
+            assert!(CostMatrix::open(&path).is_err());
+
+            for (row, values) in expected.iter().enumerate() {
+                let source = Vertex::try_from(row).unwrap();
+                if row % 7 == 3 {
+                    assert!(!compressed.is_valid(source));
+                    assert!(compressed.get(source, Vertex(1)).is_err());
+                    assert!(compressed.row(source).is_err());
+                    continue;
+                }
+                assert_eq!(&compressed.row(source).unwrap(), values);
+                for (col, &value) in values.iter().enumerate() {
+                    let target = Vertex::try_from(col).unwrap();
+                    assert_eq!(compressed.get(source, target).unwrap(), value);
+                }
+            }
+            assert!(compressed.get(Vertex(1), Vertex(cols as u32 + 1)).is_err());
+
+            let path = dir.path().join(format!("matrix_{block_len}.raw"));
+            let decompressed = compressed.decompress(&path).unwrap();
+            assert_eq!(decompressed.header(), matrix.header());
+            assert_eq!(
+                decompressed.iter_rows().collect::<Vec<_>>(),
+                matrix.iter_rows().collect::<Vec<_>>()
+            );
+        }
+
+        // a row count written by another tool must not overflow the size of the row index
+        let path = dir.path().join(format!("matrix_{DEFAULT_BLOCK_LEN}.cost"));
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all_at(&u64::MAX.to_le_bytes(), 16).unwrap();
+        assert!(CompressedCostMatrix::open(&path).is_err());
+    }
+}
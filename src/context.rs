@@ -0,0 +1,157 @@
+use std::cell::RefCell;
+
+use thread_local::ThreadLocal;
+
+use crate::dijkstra::{
+    sp_bi, sp_naiv, sssp, Backend, Dense, DicirectionalList, Dijkstra, Graph, InitDijkstra,
+};
+use crate::dimacs::{Route, Vertex};
+
+/// Represents the search structures of one worker that are reused across queries.
+///
+/// Every query resets the structures instead of allocating new ones, so after the first few
+/// queries have grown them to their working size, queries only allocate the returned route.
+/// The dense backend is the default because it is reset in constant time.
+pub struct QueryContext<Q: InitDijkstra, B: Backend = Dense> {
+    forward: Q::Data<B>,
+    backward: Q::Data<B>,
+}
+
+impl<Q: InitDijkstra, B: Backend> QueryContext<Q, B> {
+    /// Constructs the structures for queries on a graph with `size` vertices.
+    pub fn new(size: usize) -> Self {
+        Self {
+            forward: Q::Data::<B>::from((Vertex(1), size)),
+            backward: Q::Data::<B>::from((Vertex(1), size)),
+        }
+    }
+
+    /// Performs single-source shortest path computation and returns the finished search.
+    pub fn sssp<G: Graph>(&mut self, source: Vertex, graph: &G) -> &Q::Data<B> {
+        self.forward.reset(source);
+        sssp(&mut self.forward, graph);
+        &self.forward
+    }
+
+    /// Performs shortest path computation to a specific target.
+    pub fn sp_naiv<G: Graph>(
+        &mut self,
+        source: Vertex,
+        target: Vertex,
+        graph: &G,
+    ) -> Option<(u32, Route)> {
+        self.forward.reset(source);
+        sp_naiv(&mut self.forward, target, graph)
+    }
+
+    /// Performs bidirectional shortest path computation.
+    pub fn sp_bi<G: Graph>(
+        &mut self,
+        source: Vertex,
+        target: Vertex,
+        graph: &DicirectionalList<G>,
+    ) -> Option<(u32, Route)> {
+        self.forward.reset(source);
+        self.backward.reset(target);
+        sp_bi(&mut self.forward, &mut self.backward, graph)
+    }
+}
+
+/// Represents a pool with one [`QueryContext`] per thread, e.g. for rayon workers.
+pub struct ContextPool<Q: InitDijkstra, B: Backend = Dense>
+where
+    QueryContext<Q, B>: Send,
+{
+    size: usize,
+    contexts: ThreadLocal<RefCell<QueryContext<Q, B>>>,
+}
+
+impl<Q: InitDijkstra, B: Backend> ContextPool<Q, B>
+where
+    QueryContext<Q, B>: Send,
+{
+    /// Constructs an empty pool whose contexts are created for graphs with `size` vertices.
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            contexts: ThreadLocal::new(),
+        }
+    }
+
+    /// Runs `query` with the context of the current thread, creating it on first use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called again from within `query` on the same thread.
+    pub fn with<R>(&self, query: impl FnOnce(&mut QueryContext<Q, B>) -> R) -> R {
+        let context = self
+            .contexts
+            .get_or(|| RefCell::new(QueryContext::new(self.size)));
+        query(&mut context.borrow_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::dijkstra::{Hashed, NeighborList, OwnedLookup, StructuredEdges};
+    use crate::dimacs::tests::random_edges;
+    use crate::implicit_heaps::{BinaryHeap, PentaryHeapSimple};
+    use crate::pairing_heap::PairingHeap;
+
+    #[test]
+    fn reused_context() {
+        let n = 500;
+        let edges = random_edges(n, 3 * n);
+        let graph: DicirectionalList<NeighborList> = DicirectionalList::new(n, edges.into_iter());
+        let mut owned: QueryContext<BinaryHeap> = QueryContext::new(n);
+        let mut hashed: QueryContext<BinaryHeap, Hashed> = QueryContext::new(n);
+        let mut simple: QueryContext<PentaryHeapSimple> = QueryContext::new(n);
+        let mut pairing: QueryContext<PairingHeap> = QueryContext::new(n);
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let s = Vertex(rng.gen_range(1..=n as u32));
+            let t = Vertex(rng.gen_range(1..=n as u32));
+            let expected = sssp(OwnedLookup::<BinaryHeap>::from((s, n)), &graph.forward);
+            let dist = expected.get_dist(t);
+            let naiv = |(dist, _): (u32, Route)| dist;
+            assert_eq!(owned.sp_naiv(s, t, &graph.forward).map(naiv), dist);
+            assert_eq!(hashed.sp_naiv(s, t, &graph.forward).map(naiv), dist);
+            assert_eq!(simple.sp_naiv(s, t, &graph.forward).map(naiv), dist);
+            assert_eq!(pairing.sp_naiv(s, t, &graph.forward).map(naiv), dist);
+            if s != t {
+                let (bi, route) = owned.sp_bi(s, t, &graph).unwrap();
+                assert_eq!(Some(bi), dist);
+                assert_eq!((route.0.first(), route.0.last()), (Some(&t), Some(&s)));
+                assert_eq!(simple.sp_bi(s, t, &graph).map(naiv), dist);
+            }
+            let search = owned.sssp(s, &graph.forward);
+            for i in 0..n {
+                let v = Vertex::try_from(i).unwrap();
+                assert_eq!(search.get_dist(v), expected.get_dist(v));
+            }
+        }
+    }
+
+    #[test]
+    fn pool_across_threads() {
+        let n = 500;
+        let edges = random_edges(n, 3 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.into_iter());
+        let pool: ContextPool<BinaryHeap> = ContextPool::new(n);
+        let queries: Vec<(Vertex, Vertex)> = (0..200)
+            .map(|i| (Vertex(i % n as u32 + 1), Vertex((i * 7) % n as u32 + 1)))
+            .collect();
+        let dists: Vec<Option<u32>> = queries
+            .par_iter()
+            .map(|&(s, t)| pool.with(|context| context.sp_naiv(s, t, &graph).map(|(d, _)| d)))
+            .collect();
+        for (&(s, t), dist) in queries.iter().zip(dists) {
+            let expected = sp_naiv(OwnedLookup::<BinaryHeap>::from((s, n)), t, &graph);
+            assert_eq!(dist, expected.map(|(d, _)| d));
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rayon::prelude::*;
+
+use crate::dijkstra::Graph;
+use crate::dimacs::{Vertex, UNDEFINED};
+use crate::dynamic::ShortestPathTree;
+
+/// Marks a vertex that has not been reached yet.
+const UNREACHED: u64 = u64::MAX;
+
+/// Packs a tentative distance with the predecessor it was reached from, so both are updated by a
+/// single `fetch_min`. Among equal distances the smaller predecessor wins, which keeps the tree
+/// independent of the thread schedule.
+#[inline]
+fn pack(dist: u32, pred: usize) -> u64 {
+    (u64::from(dist) << 32) | pred as u64
+}
+
+#[inline]
+fn dist(packed: u64) -> u32 {
+    (packed >> 32) as u32
+}
+
+#[inline]
+fn pred(packed: u64) -> usize {
+    (packed & u64::from(u32::MAX)) as usize
+}
+
+/// Suggests a bucket width for [`delta_stepping`] from the edge weights of `graph`.
+///
+/// Following Meyer and Sanders the width is the largest weight divided by the average out-degree,
+/// so a vertex has about one light edge per unit of degree.
+pub fn default_delta<G: Graph>(graph: &G) -> u32 {
+    let n = graph.num_vertices();
+    let (mut edges, mut max_weight) = (0usize, 1u32);
+    for i in 0..n {
+        for neighbor in graph.get_neighbors(Vertex::try_from(i).unwrap()) {
+            edges += 1;
+            max_weight = u32::max(max_weight, neighbor.weight);
+        }
+    }
+    let degree = usize::max(1, edges.div_ceil(usize::max(1, n)));
+    u32::max(1, max_weight / degree as u32)
+}
+
+/// Calculate single-source shortest paths with the delta-stepping algorithm of Meyer and Sanders.
+///
+/// Vertices are kept in buckets of width `delta` by their tentative distance. The smallest
+/// non-empty bucket is settled by relaxing light edges (weight at most `delta`) of its vertices
+/// in parallel until it stays empty, then the heavy edges of everything settled in it.
+///
+/// The distances are the same as with [`crate::dijkstra::sssp`], and so are the predecessors of
+/// vertices with a single shortest path. Otherwise the predecessors may differ: `sssp` keeps the
+/// tight arc its queue happened to relax first, this keeps the one from the smallest vertex,
+/// which does not depend on the thread schedule.
+pub fn delta_stepping<G: Graph + Sync>(source: Vertex, graph: &G, delta: u32) -> ShortestPathTree {
+    assert!(delta > 0, "delta has to be positive");
+    let n = graph.num_vertices();
+    let max_weight = (0..n)
+        .into_par_iter()
+        .flat_map_iter(|i| graph.get_neighbors(Vertex::try_from(i).unwrap()))
+        .map(|neighbor| neighbor.weight)
+        .max()
+        .unwrap_or(0);
+    // tentative distances span at most max_weight, so the buckets are reused cyclically
+    let slots = (max_weight / delta) as usize + 2;
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); slots];
+    let tentative: Vec<AtomicU64> = (0..n).map(|_| AtomicU64::new(UNREACHED)).collect();
+
+    let start = usize::from(source);
+    tentative[start].store(pack(0, start), Ordering::Relaxed);
+    buckets[0].push(start);
+    let mut pending = 1;
+
+    // relaxes the light or heavy edges of `frontier`, returning the vertices that got closer
+    let relax = |frontier: &[usize], light: bool| -> Vec<usize> {
+        frontier
+            .par_iter()
+            .flat_map_iter(|&u| {
+                let du = dist(tentative[u].load(Ordering::Relaxed));
+                let tentative = &tentative;
+                graph
+                    .get_neighbors(Vertex::try_from(u).unwrap())
+                    .filter(move |neighbor| (neighbor.weight <= delta) == light)
+                    .filter_map(move |neighbor| {
+                        let v = usize::from(neighbor.to);
+                        let candidate = pack(du + neighbor.weight, u);
+                        let old = tentative[v].fetch_min(candidate, Ordering::Relaxed);
+                        (dist(candidate) < dist(old)).then_some(v)
+                    })
+            })
+            .collect()
+    };
+
+    let mut current = 0usize;
+    while pending > 0 {
+        let slot = current % slots;
+        let mut settled = Vec::new();
+        while !buckets[slot].is_empty() {
+            let mut frontier = std::mem::take(&mut buckets[slot]);
+            pending -= frontier.len();
+            frontier.sort_unstable();
+            frontier.dedup();
+            // skip entries of vertices that moved to a closer bucket since
+            frontier
+                .retain(|&v| dist(tentative[v].load(Ordering::Relaxed)) / delta == current as u32);
+            for v in relax(&frontier, true) {
+                let bucket = (dist(tentative[v].load(Ordering::Relaxed)) / delta) as usize;
+                buckets[bucket % slots].push(v);
+                pending += 1;
+            }
+            settled.extend(frontier);
+        }
+        settled.sort_unstable();
+        settled.dedup();
+        for v in relax(&settled, false) {
+            let bucket = (dist(tentative[v].load(Ordering::Relaxed)) / delta) as usize;
+            buckets[bucket % slots].push(v);
+            pending += 1;
+        }
+        current += 1;
+    }
+
+    let (dist, pred) = tentative
+        .into_iter()
+        .map(|packed| match packed.into_inner() {
+            UNREACHED => (u32::MAX, UNDEFINED),
+            packed => (dist(packed), Vertex::try_from(pred(packed)).unwrap()),
+        })
+        .unzip();
+    ShortestPathTree::from_parts(source, dist, pred)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::{sssp, Dijkstra, Neighbor, NeighborList, OwnedLookup, StructuredEdges};
+    use crate::dimacs::tests::random_edges;
+    use crate::dimacs::Edge;
+    use crate::implicit_heaps::BinaryHeap;
+
+    #[test]
+    fn same_tree_as_sssp() {
+        let n = 3000;
+        // small weights, so many vertices have several shortest paths
+        let edges: Vec<Edge> = random_edges(n, 4 * n)
+            .into_iter()
+            .map(|edge| Edge {
+                weight: edge.weight % 10 + 1,
+                ..edge
+            })
+            .collect();
+        let graph: NeighborList = StructuredEdges::new(n, edges.iter().cloned());
+        let source = Vertex(7);
+        let expected = sssp(OwnedLookup::<BinaryHeap>::from((source, n)), &graph);
+        for delta in [1, 5, default_delta(&graph), 100000] {
+            let tree = delta_stepping(source, &graph, delta);
+            for i in 0..n {
+                let v = Vertex::try_from(i).unwrap();
+                let dist = tree.get_dist(v);
+                assert_eq!(dist, expected.get_dist(v));
+                if v == source {
+                    assert_eq!(tree.get_pred(v), Some(source));
+                    continue;
+                }
+                // tails of the tight arcs into v
+                let mut tight: Vec<u32> = edges
+                    .iter()
+                    .filter(|edge| edge.to == v)
+                    .filter(|edge| {
+                        expected.get_dist(edge.from).unwrap() + edge.weight == dist.unwrap()
+                    })
+                    .map(|edge| edge.from.0)
+                    .collect();
+                tight.sort_unstable();
+                tight.dedup();
+                let pred = tree.get_pred(v).unwrap();
+                assert_eq!(pred.0, tight[0]);
+                if tight.len() == 1 {
+                    assert_eq!(pred, expected.get_meta(v).unwrap().1);
+                }
+            }
+            assert!(tree.get_path(Vertex(1)).is_some());
+        }
+    }
+
+    #[test]
+    fn unreachable_vertices() {
+        let graph: NeighborList = vec![
+            vec![Neighbor {
+                to: Vertex(2),
+                weight: 3,
+            }],
+            Vec::new(),
+            Vec::new(),
+        ];
+        let tree = delta_stepping(Vertex(1), &graph, default_delta(&graph));
+        assert_eq!(tree.get_dist(Vertex(2)), Some(3));
+        assert_eq!(tree.get_pred(Vertex(2)), Some(Vertex(1)));
+        assert_eq!(tree.get_dist(Vertex(3)), None);
+        assert_eq!(tree.get_pred(Vertex(3)), None);
+    }
+}
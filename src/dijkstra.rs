@@ -4,7 +4,8 @@ use std::collections::{
 };
 use std::fmt::Debug;
 use std::hash::BuildHasherDefault;
-use std::slice::Iter;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
 use std::usize;
 
 use nohash_hasher::{IsEnabled, NoHashHasher};
@@ -13,9 +14,21 @@ use crate::dimacs::*;
 
 /// Represents an item of a priority queue with a key and a value.
 #[derive(PartialEq, Eq, Clone, Copy)]
-pub struct Item {
-    pub key: u32,
-    pub value: Vertex,
+pub struct Item<K = u32, V = Vertex> {
+    pub key: K,
+    pub value: V,
+}
+
+/// Represents a reference to an item of an arena based priority queue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Handle(pub u32);
+
+impl From<Vertex> for Handle {
+    /// The item of the source, which is the first item of a new queue.
+    #[inline]
+    fn from(_: Vertex) -> Self {
+        Handle(0)
+    }
 }
 
 /// Represents a sortet list.
@@ -32,30 +45,177 @@ impl From<Vertex> for SortetList {
     }
 }
 
-impl PartialOrd for Item {
+impl<K: Ord, V: Eq> PartialOrd for Item<K, V> {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(other.key.cmp(&self.key))
     }
 }
 
-impl Ord for Item {
+impl<K: Ord, V: Eq> Ord for Item<K, V> {
     #[inline]
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         other.key.cmp(&self.key)
     }
 }
 
+/// Per-vertex state stored in a hash map.
+pub type HashedMeta<V> = HashMap<Vertex, V, BuildHasherDefault<NoHashHasher<u32>>>;
+
+/// A trait representing storage of per-vertex search state.
+pub trait MetaStore<V> {
+    /// Constructs an empty store for a graph with `size` vertices.
+    fn with_capacity(size: usize) -> Self;
+    fn get(&self, v: Vertex) -> Option<&V>;
+    fn get_mut(&mut self, v: Vertex) -> Option<&mut V>;
+    fn insert(&mut self, v: Vertex, value: V);
+    /// Gets the entry of `v`, inserting the value returned by `default` if there is none.
+    /// The flag tells whether the entry was inserted.
+    fn get_or_insert_with(&mut self, v: Vertex, default: impl FnOnce() -> V) -> (&mut V, bool);
+    /// Removes all entries while keeping the allocated memory.
+    fn clear(&mut self);
+}
+
+impl<V> MetaStore<V> for HashedMeta<V> {
+    #[inline]
+    fn with_capacity(size: usize) -> Self {
+        HashMap::with_capacity_and_hasher(size, BuildHasherDefault::default())
+    }
+
+    #[inline]
+    fn get(&self, v: Vertex) -> Option<&V> {
+        HashMap::get(self, &v)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, v: Vertex) -> Option<&mut V> {
+        HashMap::get_mut(self, &v)
+    }
+
+    #[inline]
+    fn insert(&mut self, v: Vertex, value: V) {
+        HashMap::insert(self, v, value);
+    }
+
+    #[inline]
+    fn get_or_insert_with(&mut self, v: Vertex, default: impl FnOnce() -> V) -> (&mut V, bool) {
+        match self.entry(v) {
+            Occupied(entry) => (entry.into_mut(), false),
+            Vacant(entry) => (entry.insert(default()), true),
+        }
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        HashMap::clear(self);
+    }
+}
+
+/// Represents per-vertex state stored in a vector indexed by vertex.
+///
+/// Every slot remembers the generation it was written in, so clearing the store only starts a
+/// new generation instead of touching all slots.
+pub struct DenseMeta<V> {
+    slots: Vec<(u32, Option<V>)>,
+    generation: u32,
+}
+
+impl<V> DenseMeta<V> {
+    #[inline]
+    fn slot(&self, v: Vertex) -> Option<&(u32, Option<V>)> {
+        self.slots
+            .get(usize::from(v))
+            .filter(|(generation, _)| *generation == self.generation)
+    }
+}
+
+impl<V> MetaStore<V> for DenseMeta<V> {
+    #[inline]
+    fn with_capacity(size: usize) -> Self {
+        let mut slots = Vec::with_capacity(size);
+        slots.resize_with(size, || (0, None));
+        Self {
+            slots,
+            generation: 1,
+        }
+    }
+
+    #[inline]
+    fn get(&self, v: Vertex) -> Option<&V> {
+        self.slot(v)?.1.as_ref()
+    }
+
+    #[inline]
+    fn get_mut(&mut self, v: Vertex) -> Option<&mut V> {
+        match self.slots.get_mut(usize::from(v)) {
+            Some((generation, value)) if *generation == self.generation => value.as_mut(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn insert(&mut self, v: Vertex, value: V) {
+        let index = usize::from(v);
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || (0, None));
+        }
+        self.slots[index] = (self.generation, Some(value));
+    }
+
+    #[inline]
+    fn get_or_insert_with(&mut self, v: Vertex, default: impl FnOnce() -> V) -> (&mut V, bool) {
+        let index = usize::from(v);
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || (0, None));
+        }
+        let slot = &mut self.slots[index];
+        let inserted = slot.0 != self.generation;
+        if inserted {
+            *slot = (self.generation, Some(default()));
+        }
+        //can safely unwrap because slots of the current generation are always set
+        (slot.1.as_mut().unwrap(), inserted)
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        if self.generation == u32::MAX {
+            self.slots.fill_with(|| (0, None));
+            self.generation = 0;
+        }
+        self.generation += 1;
+    }
+}
+
+/// A trait selecting how the search structures store per-vertex state.
+pub trait Backend {
+    type Store<V>: MetaStore<V>;
+}
+
+/// Stores per-vertex state in a hash map, its memory grows with the number of reached vertices.
+pub struct Hashed;
+
+impl Backend for Hashed {
+    type Store<V> = HashedMeta<V>;
+}
+
+/// Stores per-vertex state in a vector sized to the graph, which avoids hashing and can be
+/// cleared in constant time.
+pub struct Dense;
+
+impl Backend for Dense {
+    type Store<V> = DenseMeta<V>;
+}
+
 /// Represents a search structure.
-pub struct Search<T: DecreaseKey> {
+pub struct Search<T: DecreaseKey, B: Backend = Hashed> {
     /// The priority queue used for searching.
     pub queue: T,
     /// Meta-information about vertices with entries like (reference to the heap item, distance, previous node).
-    pub meta:
-        HashMap<Vertex, (T::RefType, T::Key, T::Value), BuildHasherDefault<NoHashHasher<T::Key>>>,
+    pub meta: B::Store<(T::RefType, T::Key, T::Value)>,
 }
 
-impl<T: DecreaseKey> From<(Vertex, usize)> for Search<T> {
+impl<T: DecreaseKey, B: Backend> From<(Vertex, usize)> for Search<T, B> {
     #[inline]
     fn from(tuple: (Vertex, usize)) -> Self {
         let (source, size) = tuple;
@@ -64,56 +224,56 @@ impl<T: DecreaseKey> From<(Vertex, usize)> for Search<T> {
             T::Key::from(0),
             T::Value::from(source),
         );
-        let mut map = HashMap::with_capacity_and_hasher(size, BuildHasherDefault::default());
-        map.insert(source, item);
+        let mut meta = B::Store::with_capacity(size);
+        meta.insert(source, item);
         Self {
             queue: T::from(source),
-            meta: map,
+            meta,
         }
     }
 }
 
 // Represent a search structure with its own lookup implementation
-pub struct OwnedLookup<T: DecreaseKey> {
+pub struct OwnedLookup<T: DecreaseKey, B: Backend = Hashed> {
     /// The priority queue used for searching.
     pub queue: T,
     /// Meta-information about vertices with entries like (distance, previous node).
-    pub meta: HashMap<Vertex, (T::Key, T::Value), BuildHasherDefault<NoHashHasher<T::Key>>>,
+    pub meta: B::Store<(T::Key, T::Value)>,
 }
 
-impl<T: DecreaseKey> From<(Vertex, usize)> for OwnedLookup<T> {
+impl<T: DecreaseKey, B: Backend> From<(Vertex, usize)> for OwnedLookup<T, B> {
     #[inline]
     fn from(tuple: (Vertex, usize)) -> Self {
         let (source, size) = tuple;
         let item = (0.into(), T::Value::from(source));
-        let mut map = HashMap::with_capacity_and_hasher(size, BuildHasherDefault::default());
-        map.insert(source, item);
+        let mut meta = B::Store::with_capacity(size);
+        meta.insert(source, item);
         Self {
             queue: T::from(source),
-            meta: map,
+            meta,
         }
     }
 }
 
 // Represent a search structure without lookup implementation
-pub struct NoLookup<T: PriorityQueue> {
+pub struct NoLookup<T: PriorityQueue, B: Backend = Hashed> {
     /// The priority queue used for searching.
     pub queue: T,
-    /// Meta-information about vertices with entries like (distance, previous node).
-    /// a distance is only set when it is final.
-    pub meta: HashMap<Vertex, (Option<T::Key>, T::Value), BuildHasherDefault<NoHashHasher<T::Key>>>,
+    /// Meta-information about vertices with entries like (distance, previous node, final).
+    /// the distance is tentative until the vertex is popped.
+    pub meta: B::Store<(T::Key, T::Value, bool)>,
 }
 
-impl<T: PriorityQueue> From<(Vertex, usize)> for NoLookup<T> {
+impl<T: PriorityQueue, B: Backend> From<(Vertex, usize)> for NoLookup<T, B> {
     #[inline]
     fn from(tuple: (Vertex, usize)) -> Self {
         let (value, size) = tuple;
-        let item = (None, T::Value::from(value));
-        let mut map = HashMap::with_capacity_and_hasher(size, BuildHasherDefault::default());
-        map.insert(value, item);
+        let item = (0.into(), T::Value::from(value), false);
+        let mut meta = B::Store::with_capacity(size);
+        meta.insert(value, item);
         Self {
             queue: T::from(value),
-            meta: map,
+            meta,
         }
     }
 }
@@ -121,12 +281,34 @@ impl<T: PriorityQueue> From<(Vertex, usize)> for NoLookup<T> {
 /// A trait representing a priority queue.
 pub trait PriorityQueue: From<Vertex> {
     type RefType: From<Vertex> + Debug + Clone;
-    type Key: From<u32> + Into<u32> + IsEnabled + Eq + Debug + Copy;
+    type Key: From<u32> + IsEnabled + Eq + Debug + Copy;
     type Value: From<Vertex> + Into<Vertex> + Eq + Debug + Copy;
 
     fn is_empty(&self) -> bool;
     fn pop(&mut self) -> Option<(Self::Key, Self::Value)>;
     fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType;
+    /// Empties the queue and inserts `source` with key 0, like `From<Vertex>` but keeping the
+    /// allocated memory.
+    fn reset(&mut self, source: Vertex);
+}
+
+/// Fails to compile unless `T` is `Send`, which keeps the queues usable from worker threads.
+pub(crate) const fn assert_send<T: Send>() {}
+
+const _: () = assert_send::<SortetList>();
+
+/// A trait representing the sift operations of an implicit heap, which the `PriorityQueue` derive
+/// builds upon.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` derives `PriorityQueue` but does not implement `Heapify`",
+    label = "`bubble_up` and `bubble_down` are missing",
+    note = "implement `Heapify` for `{Self}` to restore the heap order after a push or a pop"
+)]
+pub trait Heapify {
+    /// Moves the item at `index` up until its parent is not larger.
+    fn bubble_up(&mut self, index: usize);
+    /// Moves the root down until no child is smaller.
+    fn bubble_down(&mut self);
 }
 
 /// A trait representing a priority queue with support for key decrease operation.
@@ -136,7 +318,8 @@ pub trait DecreaseKey: PriorityQueue {
 
 /// A trait representing the ability to do BFS seasch for the Dijkstra algorithm.
 pub trait Dijkstra {
-    type Queue: PriorityQueue;
+    /// The queue of the search, whose keys are distances.
+    type Queue: PriorityQueue<Key: Into<u32>>;
 
     /// explore new node
     fn explore(
@@ -162,6 +345,9 @@ pub trait Dijkstra {
         <Self::Queue as PriorityQueue>::Value,
     )>;
 
+    /// Prepares the search for a new query from `source`, keeping the allocated memory.
+    fn reset(&mut self, source: Vertex);
+
     fn get_path(&self, target: Vertex) -> Option<Route> {
         let mut path = Vec::new();
         let mut head = target;
@@ -184,35 +370,81 @@ pub trait Dijkstra {
     }
 }
 
+/// Allows running a search owned by someone else, e.g. a [`crate::context::QueryContext`].
+impl<D: Dijkstra> Dijkstra for &mut D {
+    type Queue = D::Queue;
+
+    #[inline]
+    fn explore(
+        &mut self,
+        from: <Self::Queue as PriorityQueue>::Value,
+        key: <Self::Queue as PriorityQueue>::Key,
+        e: &Neighbor,
+    ) {
+        (**self).explore(from, key, e)
+    }
+
+    #[inline]
+    fn pop_min(
+        &mut self,
+    ) -> Option<(
+        <Self::Queue as PriorityQueue>::Key,
+        <Self::Queue as PriorityQueue>::Value,
+    )> {
+        (**self).pop_min()
+    }
+
+    #[inline]
+    fn get_meta(
+        &self,
+        target: Vertex,
+    ) -> Option<(
+        <Self::Queue as PriorityQueue>::Key,
+        <Self::Queue as PriorityQueue>::Value,
+    )> {
+        (**self).get_meta(target)
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        (**self).reset(source)
+    }
+}
+
 pub trait InitDijkstra: PriorityQueue {
-    type Data: From<(Vertex, usize)> + Dijkstra;
+    type Data<B: Backend>: From<(Vertex, usize)> + Dijkstra;
 
     #[inline]
     fn init_dijkstra(source: Vertex, size: usize) -> impl Dijkstra {
-        Self::Data::from((source, size))
+        Self::Data::<Hashed>::from((source, size))
+    }
+
+    /// Initializes a search storing its per-vertex state in the backend `B`.
+    #[inline]
+    fn init_dijkstra_with<B: Backend>(source: Vertex, size: usize) -> impl Dijkstra {
+        Self::Data::<B>::from((source, size))
     }
 }
 
-impl<T: DecreaseKey> Dijkstra for Search<T> {
+impl<T: DecreaseKey, B: Backend> Dijkstra for Search<T, B>
+where
+    T::Key: Into<u32>,
+{
     type Queue = T;
 
     #[inline]
     fn explore(&mut self, from: T::Value, key: T::Key, e: &Neighbor) {
         let alt: u32 = key.into() + e.weight;
-        let explored = self.meta.entry(e.to.into());
-        match explored {
-            Occupied(mut entry) => {
-                let (link, dist, prev) = entry.get_mut();
-                if alt < (*dist).into() {
-                    self.queue.decrease_key(link.clone(), alt.into());
-                    *dist = alt.into();
-                    *prev = from;
-                }
-            }
-            Vacant(entry) => {
-                let link = self.queue.push(alt.into(), e.to.into());
-                entry.insert((link, alt.into(), from));
-            }
+        let queue = &mut self.queue;
+        let (explored, inserted) = self.meta.get_or_insert_with(e.to, || {
+            let link = queue.push(alt.into(), e.to.into());
+            (link, alt.into(), from)
+        });
+        let (link, dist, prev) = explored;
+        if !inserted && alt < (*dist).into() {
+            queue.decrease_key(link.clone(), alt.into());
+            *dist = alt.into();
+            *prev = from;
         }
     }
 
@@ -229,33 +461,44 @@ impl<T: DecreaseKey> Dijkstra for Search<T> {
         <Self::Queue as PriorityQueue>::Key,
         <Self::Queue as PriorityQueue>::Value,
     )> {
-        if let Some((_, dist, prev)) = self.meta.get(&target) {
+        if let Some((_, dist, prev)) = self.meta.get(target) {
             return Some((*dist, *prev));
         }
         None
     }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        let item = (
+            T::RefType::from(source),
+            T::Key::from(0),
+            T::Value::from(source),
+        );
+        self.queue.reset(source);
+        self.meta.clear();
+        self.meta.insert(source, item);
+    }
 }
 
-impl<T: DecreaseKey> Dijkstra for OwnedLookup<T> {
+impl<T: DecreaseKey, B: Backend> Dijkstra for OwnedLookup<T, B>
+where
+    T::Key: Into<u32>,
+{
     type Queue = T;
 
     #[inline]
     fn explore(&mut self, from: T::Value, key: T::Key, e: &Neighbor) {
         let alt: u32 = key.into() + e.weight;
-        let explored = self.meta.entry(e.to.into());
-        match explored {
-            Occupied(mut entry) => {
-                let (dist, prev) = entry.get_mut();
-                if alt < (*dist).into() {
-                    self.queue.decrease_key(e.to.into(), alt.into());
-                    *dist = alt.into();
-                    *prev = from;
-                }
-            }
-            Vacant(entry) => {
-                self.queue.push(alt.into(), e.to.into());
-                entry.insert((alt.into(), from));
-            }
+        let queue = &mut self.queue;
+        let (explored, inserted) = self.meta.get_or_insert_with(e.to, || {
+            queue.push(alt.into(), e.to.into());
+            (alt.into(), from)
+        });
+        let (dist, prev) = explored;
+        if !inserted && alt < (*dist).into() {
+            queue.decrease_key(e.to.into(), alt.into());
+            *dist = alt.into();
+            *prev = from;
         }
     }
 
@@ -272,41 +515,49 @@ impl<T: DecreaseKey> Dijkstra for OwnedLookup<T> {
         <Self::Queue as PriorityQueue>::Key,
         <Self::Queue as PriorityQueue>::Value,
     )> {
-        self.meta.get(&target).copied()
+        self.meta.get(target).copied()
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        self.queue.reset(source);
+        self.meta.clear();
+        self.meta.insert(source, (0.into(), T::Value::from(source)));
     }
 }
 
-impl<T: PriorityQueue> Dijkstra for NoLookup<T> {
+impl<T: PriorityQueue, B: Backend> Dijkstra for NoLookup<T, B>
+where
+    T::Key: Into<u32>,
+{
     type Queue = T;
 
     #[inline]
     fn explore(&mut self, from: T::Value, key: T::Key, e: &Neighbor) {
         let alt: u32 = key.into() + e.weight;
-        self.queue.push(alt.into(), e.to.into());
-        match self.meta.get_mut(&e.to) {
-            None => {
-                self.meta.insert(e.to, (None, from));
-            }
-            Some((None, prev)) => {
-                *prev = from;
-            }
-            _ => (),
+        let (explored, inserted) = self
+            .meta
+            .get_or_insert_with(e.to, || (alt.into(), from, false));
+        let (dist, prev, settled) = explored;
+        if inserted || (!*settled && alt < (*dist).into()) {
+            *dist = alt.into();
+            *prev = from;
+            self.queue.push(alt.into(), e.to.into());
         }
     }
 
     #[inline]
     fn pop_min(&mut self) -> Option<(T::Key, T::Value)> {
         while let Some((key, value)) = self.queue.pop() {
-            let (extended, _) = self.meta.get_mut(&value.into()).unwrap();
-            if let Some(_) = extended {
-                //skip expanded items
+            let (dist, _, settled) = self.meta.get_mut(value.into()).unwrap();
+            //skip expanded and outdated items
+            if *settled || key != *dist {
                 continue;
-            } else {
-                *extended = Some(key);
-                return Some((key, value));
             }
+            *settled = true;
+            return Some((key, value));
         }
-        return None;
+        None
     }
 
     #[inline]
@@ -317,11 +568,19 @@ impl<T: PriorityQueue> Dijkstra for NoLookup<T> {
         <Self::Queue as PriorityQueue>::Key,
         <Self::Queue as PriorityQueue>::Value,
     )> {
-        if let Some((Some(dist), prev)) = self.meta.get(&target) {
+        if let Some((dist, prev, true)) = self.meta.get(target) {
             return Some((*dist, *prev));
         }
         None
     }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        self.queue.reset(source);
+        self.meta.clear();
+        self.meta
+            .insert(source, (0.into(), T::Value::from(source), false));
+    }
 }
 
 impl PriorityQueue for SortetList {
@@ -356,10 +615,19 @@ impl PriorityQueue for SortetList {
             }
         }
     }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        self.inner.clear();
+        self.inner.push(Item {
+            key: 0,
+            value: source,
+        });
+    }
 }
 
 impl InitDijkstra for SortetList {
-    type Data = NoLookup<Self>;
+    type Data<B: Backend> = NoLookup<Self, B>;
 }
 
 /// Represents a neighboring vertex with its weight.
@@ -383,7 +651,7 @@ impl From<Edge> for Neighbor {
 pub type NeighborList = Vec<Vec<Neighbor>>;
 
 /// Represents a bidirectional list of edges.
-pub struct DicirectionalList<T: StructuredEdges> {
+pub struct DicirectionalList<T: Graph> {
     pub forward: T,
     pub backward: T,
 }
@@ -409,10 +677,26 @@ impl<T: StructuredEdges> DicirectionalList<T> {
     }
 }
 
+/// A trait for structures that can be searched by their neighbors.
+pub trait Graph {
+    fn num_vertices(&self) -> usize;
+    fn get_neighbors(&self, u: Vertex) -> impl Iterator<Item = &Neighbor>;
+}
+
 /// A trait for structures containing structured edges.
-pub trait StructuredEdges {
+pub trait StructuredEdges: Graph {
     fn new(n: usize, edges: impl Iterator<Item = Edge>) -> Self;
-    fn get_neighbors(&self, u: Vertex) -> Iter<Neighbor>;
+}
+
+impl Graph for NeighborList {
+    #[inline]
+    fn num_vertices(&self) -> usize {
+        self.len()
+    }
+    #[inline]
+    fn get_neighbors(&self, u: Vertex) -> impl Iterator<Item = &Neighbor> {
+        self[usize::from(u)].iter()
+    }
 }
 
 impl StructuredEdges for NeighborList {
@@ -425,17 +709,38 @@ impl StructuredEdges for NeighborList {
         }
         return out_edges;
     }
+}
+
+/// A trait for structures whose edge weights can be changed in place.
+pub trait MutableEdges: StructuredEdges {
+    /// Sets the weight of the arc `from -> to` and returns its previous weight,
+    /// `None` if there is no such arc.
+    fn set_weight(&mut self, from: Vertex, to: Vertex, weight: u32) -> Option<u32>;
+}
+
+impl MutableEdges for NeighborList {
     #[inline]
-    fn get_neighbors(&self, u: Vertex) -> Iter<Neighbor> {
-        self[usize::from(u)].iter()
+    fn set_weight(&mut self, from: Vertex, to: Vertex, weight: u32) -> Option<u32> {
+        let e = self[usize::from(from)].iter_mut().find(|e| e.to == to)?;
+        Some(std::mem::replace(&mut e.weight, weight))
+    }
+}
+
+impl<T: MutableEdges> DicirectionalList<T> {
+    /// Sets the weight of the arc `from -> to` in both directions and returns its previous
+    /// weight, `None` if there is no such arc.
+    pub fn set_weight(&mut self, from: Vertex, to: Vertex, weight: u32) -> Option<u32> {
+        self.backward.set_weight(to, from, weight);
+        self.forward.set_weight(from, to, weight)
     }
 }
 
 #[inline]
 /// Performs single-source shortest path computation.
-pub fn sssp<D>(mut source: D, edges: &NeighborList) -> D
+pub fn sssp<D, G>(mut source: D, edges: &G) -> D
 where
     D: Dijkstra,
+    G: Graph,
 {
     while let Some((dist, u)) = source.pop_min() {
         // update neighbors of u
@@ -448,9 +753,10 @@ where
 
 /// Performs shortest path computation to a specific target.
 #[inline]
-pub fn sp_naiv<D>(mut source: D, target: Vertex, edges: &NeighborList) -> Option<(u32, Route)>
+pub fn sp_naiv<D, G>(mut source: D, target: Vertex, edges: &G) -> Option<(u32, Route)>
 where
     D: Dijkstra,
+    G: Graph,
 {
     while let Some((dist, u)) = source.pop_min() {
         if u.into() == target {
@@ -467,16 +773,18 @@ where
 
 /// Performs bidirectional shortest path computation.
 #[inline]
-pub fn sp_bi<D>(
+pub fn sp_bi<D, G>(
     mut source: D,
     mut target: D,
-    edges: &DicirectionalList<NeighborList>,
+    edges: &DicirectionalList<G>,
 ) -> Option<(u32, Route)>
 where
     D: Dijkstra,
+    G: Graph,
 {
     let mut path_len = u32::MAX;
-    let mut bridge = Vertex(0);
+    // the arc connecting both searches, its tail is reached from the source, its head from the target
+    let mut bridge = (Vertex(0), Vertex(0));
 
     while let (Some((dist_u, u)), Some((dist_v, v))) = (source.pop_min(), target.pop_min()) {
         // update neighbors of u
@@ -486,7 +794,7 @@ where
                 let con = dist_u.into() + e.weight + x;
                 if path_len > con {
                     path_len = con;
-                    bridge = e.to;
+                    bridge = (u.into(), e.to);
                 }
             }
         }
@@ -497,19 +805,151 @@ where
                 let con = dist_v.into() + e.weight + x;
                 if path_len > con {
                     path_len = con;
-                    bridge = e.to;
+                    bridge = (e.to, v.into());
                 }
             }
         }
         if dist_u.into() + dist_v.into() >= path_len {
-            let mut forward = source.get_path(bridge).unwrap();
-            let mut backward = target.get_path(bridge).unwrap();
-            backward.reverse();
-            forward.join(&mut backward);
-            return Some((path_len, forward));
+            break;
+        }
+    }
+    // a side only runs out of vertices before the searches meet if the target is unreachable,
+    // as each side stops at the latest when it scans the other end of the path
+    if path_len == u32::MAX {
+        return None;
+    }
+    // both ends of the bridge have a distance, so their paths exist
+    let mut forward = source.get_path(bridge.0).unwrap();
+    let mut backward = target.get_path(bridge.1).unwrap();
+    backward.0.reverse();
+    if bridge.0 == bridge.1 {
+        backward.0.pop();
+    }
+    backward.join(&mut forward);
+    Some((path_len, backward))
+}
+
+/// Represents the state shared by the two searches of [`sp_bi_parallel`].
+struct Meeting {
+    /// Distance of every vertex settled by the forward search, `u32::MAX` if not settled yet.
+    forward: Vec<AtomicU32>,
+    /// Distance of every vertex settled by the backward search, `u32::MAX` if not settled yet.
+    backward: Vec<AtomicU32>,
+    /// Key of the last vertex popped by the forward and backward search, `u32::MAX` once it ran
+    /// out of vertices.
+    forward_radius: AtomicU32,
+    backward_radius: AtomicU32,
+    /// Length of the shortest connection found so far.
+    path_len: AtomicU32,
+    /// The arc of that connection, only changed together with `path_len`.
+    bridge: Mutex<(Vertex, Vertex)>,
+    /// Set once a search proved the connection optimal.
+    done: AtomicBool,
+}
+
+impl Meeting {
+    /// Records a connection of length `con` over the arc `bridge` if it is the shortest so far.
+    #[inline]
+    fn connect(&self, con: u32, bridge: (Vertex, Vertex)) {
+        if con < self.path_len.load(Ordering::SeqCst) {
+            let mut best = self.bridge.lock().unwrap();
+            // checked again under the lock as the other search may have connected meanwhile
+            if con < self.path_len.load(Ordering::SeqCst) {
+                self.path_len.store(con, Ordering::SeqCst);
+                *best = bridge;
+            }
         }
     }
-    None
+}
+
+/// Runs one direction of [`sp_bi_parallel`] until the searches meet.
+fn meet<D, G>(search: &mut D, graph: &G, meeting: &Meeting, forward: bool)
+where
+    D: Dijkstra,
+    G: Graph,
+{
+    let (own, other) = if forward {
+        (&meeting.forward, &meeting.backward)
+    } else {
+        (&meeting.backward, &meeting.forward)
+    };
+    let (own_radius, other_radius) = if forward {
+        (&meeting.forward_radius, &meeting.backward_radius)
+    } else {
+        (&meeting.backward_radius, &meeting.forward_radius)
+    };
+    while !meeting.done.load(Ordering::SeqCst) {
+        let Some((dist, u)) = search.pop_min() else {
+            // every vertex this side can reach was scanned and published, so the other side
+            // finds the best connection with its next scan
+            own_radius.store(u32::MAX, Ordering::SeqCst);
+            break;
+        };
+        let (dist_u, u): (u32, Vertex) = (dist.into(), u.into());
+        // only settled vertices are published, so both ends of a bridge have a path even for
+        // searches that do not keep paths of tentative vertices. Of an arc between vertices
+        // settled by both sides, the side that published last sees the other end.
+        own[usize::from(u)].store(dist_u, Ordering::SeqCst);
+        own_radius.store(dist_u, Ordering::SeqCst);
+        for e in graph.get_neighbors(u) {
+            search.explore(u.into(), dist, e);
+            let x = other[usize::from(e.to)].load(Ordering::SeqCst);
+            if x != u32::MAX {
+                let bridge = if forward { (u, e.to) } else { (e.to, u) };
+                meeting.connect(dist_u + e.weight + x, bridge);
+            }
+        }
+        // the radius of the other side only grows, so a stale value errs on the safe side
+        let radii = dist_u.saturating_add(other_radius.load(Ordering::SeqCst));
+        if radii >= meeting.path_len.load(Ordering::SeqCst) {
+            meeting.done.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Performs bidirectional shortest path computation with the forward and backward search
+/// running concurrently on two threads.
+///
+/// Both searches publish the distances of the vertices they settled and the radius they settled
+/// so far, each side connects its arcs with the settled vertices of the other and stops once the
+/// radii sum up to the shortest connection. Returns the same as [`sp_bi`].
+pub fn sp_bi_parallel<D, G>(
+    mut source: D,
+    mut target: D,
+    edges: &DicirectionalList<G>,
+) -> Option<(u32, Route)>
+where
+    D: Dijkstra + Send,
+    G: Graph + Sync,
+{
+    let n = edges.forward.num_vertices();
+    let meeting = Meeting {
+        forward: (0..n).map(|_| AtomicU32::new(u32::MAX)).collect(),
+        backward: (0..n).map(|_| AtomicU32::new(u32::MAX)).collect(),
+        forward_radius: AtomicU32::new(0),
+        backward_radius: AtomicU32::new(0),
+        path_len: AtomicU32::new(u32::MAX),
+        bridge: Mutex::new((Vertex(0), Vertex(0))),
+        done: AtomicBool::new(false),
+    };
+    std::thread::scope(|scope| {
+        scope.spawn(|| meet(&mut target, &edges.backward, &meeting, false));
+        meet(&mut source, &edges.forward, &meeting, true);
+    });
+    let path_len = meeting.path_len.into_inner();
+    if path_len == u32::MAX {
+        return None;
+    }
+    let bridge = meeting.bridge.into_inner().unwrap();
+    // both ends of the bridge were settled, so their paths exist
+    let mut forward = source.get_path(bridge.0).unwrap();
+    let mut backward = target.get_path(bridge.1).unwrap();
+    backward.0.reverse();
+    if bridge.0 == bridge.1 {
+        backward.0.pop();
+    }
+    backward.join(&mut forward);
+    Some((path_len, backward))
 }
 
 #[cfg(test)]
@@ -522,9 +962,13 @@ mod tests {
     use std::io::Write;
     use std::{fs::File, path::Path};
 
+    use crate::bucket_queue::BucketQueue;
+    use crate::fibonacci_heap::FibonacciHeap;
     use crate::implicit_heaps::BinaryHeap;
     use crate::implicit_heaps::BinaryHeapSimple;
     use crate::pairing_heap::PairingHeap;
+    use crate::radix_heap::RadixHeap;
+    use crate::rank_pairing_heap::RankPairingHeap;
 
     use super::*;
     use rand::{thread_rng, Rng};
@@ -556,8 +1000,9 @@ mod tests {
         //pop
         for _ in 0..n {
             let (key, popped) = dijkstra.pop_min().unwrap();
-            let (stored_key, _) = dijkstra.meta.get(&popped).unwrap();
-            assert_eq!(key, stored_key.unwrap());
+            let (stored_key, _, settled) = dijkstra.meta.get(&popped).unwrap();
+            assert!(*settled);
+            assert_eq!(key, *stored_key);
             assert!(key >= highest_min);
             highest_min = u32::max(highest_min, key);
         }
@@ -565,6 +1010,210 @@ mod tests {
         assert_eq!(None, dijkstra.pop_min());
     }
 
+    #[test]
+    fn dense_meta_clear() {
+        let mut meta: DenseMeta<u32> = MetaStore::with_capacity(4);
+        meta.insert(Vertex(2), 7);
+        // grows past the initial capacity
+        let (value, inserted) = meta.get_or_insert_with(Vertex(10), || 3);
+        assert_eq!((*value, inserted), (3, true));
+        let (value, inserted) = meta.get_or_insert_with(Vertex(10), || 5);
+        assert_eq!((*value, inserted), (3, false));
+        meta.clear();
+        assert_eq!(meta.get(Vertex(2)), None);
+        assert_eq!(meta.get_mut(Vertex(10)), None);
+        meta.generation = u32::MAX;
+        meta.insert(Vertex(1), 1);
+        meta.clear();
+        assert_eq!(meta.get(Vertex(1)), None);
+        meta.insert(Vertex(1), 2);
+        assert_eq!(meta.get(Vertex(1)), Some(&2));
+    }
+
+    #[test]
+    fn dense_matches_hashed() {
+        let n = 2000;
+        let edges = crate::dimacs::tests::random_edges(n, 4 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.into_iter());
+        let source = Vertex(1);
+        let expected = sssp(OwnedLookup::<BinaryHeap, Hashed>::from((source, n)), &graph);
+        let owned = sssp(OwnedLookup::<BinaryHeap, Dense>::from((source, n)), &graph);
+        let search = sssp(Search::<PairingHeap, Dense>::from((source, n)), &graph);
+        let no_lookup = sssp(
+            NoLookup::<BinaryHeapSimple, Dense>::from((source, n)),
+            &graph,
+        );
+        let init = sssp(BinaryHeap::init_dijkstra_with::<Dense>(source, n), &graph);
+        for i in 0..n {
+            let v = Vertex::try_from(i).unwrap();
+            let dist = expected.get_dist(v);
+            assert_eq!(owned.get_dist(v), dist);
+            assert_eq!(search.get_dist(v), dist);
+            assert_eq!(no_lookup.get_dist(v), dist);
+            assert_eq!(init.get_dist(v), dist);
+        }
+    }
+
+    /// Sums the weights of the shortest arcs along `route`, which leads from its last vertex to
+    /// its first one.
+    fn route_len(graph: &NeighborList, route: &Route) -> u32 {
+        route
+            .0
+            .windows(2)
+            .map(|arc| {
+                graph
+                    .get_neighbors(arc[1])
+                    .filter(|e| e.to == arc[0])
+                    .map(|e| e.weight)
+                    .min()
+                    .unwrap()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn no_lookup_paths() {
+        let n = 2000;
+        let edges = crate::dimacs::tests::random_edges(n, 4 * n);
+        let graph: DicirectionalList<NeighborList> = DicirectionalList::new(n, edges.into_iter());
+        // a predecessor is only replaced by a shorter one, so paths are as long as distances
+        let tree = sssp(
+            NoLookup::<BinaryHeapSimple>::from((Vertex(1), n)),
+            &graph.forward,
+        );
+        for i in 0..n {
+            let v = Vertex::try_from(i).unwrap();
+            let route = tree.get_path(v).unwrap();
+            assert_eq!(route_len(&graph.forward, &route), tree.get_dist(v).unwrap());
+        }
+        // the bridge of both searches has settled ends, whose paths exist
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let s = Vertex::try_from(rng.gen_range(0..n)).unwrap();
+            let t = Vertex::try_from(rng.gen_range(0..n)).unwrap();
+            if s == t {
+                continue;
+            }
+            let expected = sp_naiv(OwnedLookup::<BinaryHeap>::from((s, n)), t, &graph.forward);
+            let (dist, route) = sp_bi(
+                NoLookup::<BinaryHeapSimple>::from((s, n)),
+                NoLookup::<BinaryHeapSimple>::from((t, n)),
+                &graph,
+            )
+            .unwrap();
+            assert_eq!(dist, expected.unwrap().0);
+            assert_eq!(route.0.first(), Some(&t));
+            assert_eq!(route.0.last(), Some(&s));
+            assert_eq!(route_len(&graph.forward, &route), dist);
+        }
+    }
+
+    #[test]
+    fn parallel_matches_bi() {
+        let n = 2000;
+        let edges = crate::dimacs::tests::random_edges(n, 3 * n);
+        let mut graph: DicirectionalList<NeighborList> =
+            DicirectionalList::new(n + 1, edges.into_iter());
+        // an isolated vertex is unreachable
+        graph.forward.push(Vec::new());
+        graph.backward.push(Vec::new());
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let s = Vertex::try_from(rng.gen_range(0..n)).unwrap();
+            let t = Vertex::try_from(rng.gen_range(0..n)).unwrap();
+            let expected = sp_bi(
+                OwnedLookup::<BinaryHeap>::from((s, n + 2)),
+                OwnedLookup::<BinaryHeap>::from((t, n + 2)),
+                &graph,
+            );
+            let search = sp_bi_parallel(
+                Search::<PairingHeap>::from((s, n + 2)),
+                Search::<PairingHeap>::from((t, n + 2)),
+                &graph,
+            );
+            // searches without lookup only know paths of settled vertices
+            let no_lookup = sp_bi_parallel(
+                NoLookup::<BinaryHeapSimple>::from((s, n + 2)),
+                NoLookup::<BinaryHeapSimple>::from((t, n + 2)),
+                &graph,
+            );
+            let expected = expected.unwrap().0;
+            for (dist, route) in [search.unwrap(), no_lookup.unwrap()] {
+                assert_eq!(dist, expected);
+                // the route leads from the target back to the source over arcs summing up to
+                // dist
+                assert_eq!(route.0.first(), Some(&t));
+                assert_eq!(route.0.last(), Some(&s));
+                assert_eq!(route_len(&graph.forward, &route), dist);
+            }
+        }
+        let isolated = Vertex::try_from(n + 1).unwrap();
+        let unreachable = sp_bi_parallel(
+            OwnedLookup::<BinaryHeap>::from((Vertex(1), n + 2)),
+            OwnedLookup::<BinaryHeap>::from((isolated, n + 2)),
+            &graph,
+        );
+        assert!(unreachable.is_none());
+        // one search can run out of vertices before the other one started
+        let path: DicirectionalList<NeighborList> = DicirectionalList::new(
+            3,
+            [(1, 2, 5), (2, 3, 7)]
+                .into_iter()
+                .map(|(from, to, weight)| Edge {
+                    from: Vertex(from),
+                    to: Vertex(to),
+                    weight,
+                }),
+        );
+        for _ in 0..100 {
+            let result = sp_bi_parallel(
+                Search::<PairingHeap>::from((Vertex(1), 3)),
+                Search::<PairingHeap>::from((Vertex(3), 3)),
+                &path,
+            );
+            assert_eq!(result.map(|(dist, _)| dist), Some(12));
+            let result = sp_bi_parallel(
+                NoLookup::<BinaryHeapSimple>::from((Vertex(1), 3)),
+                NoLookup::<BinaryHeapSimple>::from((Vertex(3), 3)),
+                &path,
+            );
+            assert_eq!(result.map(|(dist, _)| dist), Some(12));
+        }
+    }
+
+    #[test]
+    fn bi_matches_naiv_on_disconnected_graphs() {
+        // sparse graphs that are not strongly connected, so many targets are unreachable and
+        // one search often scans everything it reaches
+        let n = 8;
+        let mut rng = thread_rng();
+        for _ in 0..200 {
+            let edges: Vec<Edge> = (0..10)
+                .map(|_| Edge {
+                    from: Vertex(rng.gen_range(1..=n as u32)),
+                    to: Vertex(rng.gen_range(1..=n as u32)),
+                    weight: rng.gen_range(1..20),
+                })
+                .collect();
+            let graph: NeighborList = StructuredEdges::new(n, edges.clone().into_iter());
+            let bigraph: DicirectionalList<NeighborList> =
+                DicirectionalList::new(n, edges.into_iter());
+            for (s, t) in (1..=n as u32).flat_map(|s| (1..=n as u32).map(move |t| (s, t))) {
+                if s == t {
+                    continue;
+                }
+                let (s, t) = (Vertex(s), Vertex(t));
+                let naiv = sp_naiv(OwnedLookup::<BinaryHeap>::from((s, n)), t, &graph);
+                let bi = sp_bi(
+                    OwnedLookup::<BinaryHeap>::from((s, n)),
+                    OwnedLookup::<BinaryHeap>::from((t, n)),
+                    &bigraph,
+                );
+                assert_eq!(bi.map(|(dist, _)| dist), naiv.map(|(dist, _)| dist));
+            }
+        }
+    }
+
     macro_rules! sssp_test {
         // using a ty token type for macthing datatypes passed to maccro
         ($name:ident,$T:ident, $Q:ident) => {
@@ -639,6 +1288,10 @@ mod tests {
     sssp_test!(sssp_test_pairing, Search, PairingHeap);
     sssp_test!(sssp_test_list, NoLookup, SortetList);
     sssp_test!(sssp_test_simple, NoLookup, BinaryHeapSimple);
+    sssp_test!(sssp_test_radix, OwnedLookup, RadixHeap);
+    sssp_test!(sssp_test_bucket, OwnedLookup, BucketQueue);
+    sssp_test!(sssp_test_fibonacci, Search, FibonacciHeap);
+    sssp_test!(sssp_test_rank_pairing, Search, RankPairingHeap);
 
     #[test]
     fn sp_test() {
@@ -2,7 +2,8 @@ use std::fmt::Display;
 use std::io;
 use std::num::TryFromIntError;
 use std::{
-    fs::File,
+    fs::TryLockError,
+    fs::{File, OpenOptions},
     io::{BufRead, BufReader, Seek, SeekFrom},
     num::ParseIntError,
     os::unix::fs::FileExt,
@@ -11,6 +12,10 @@ use std::{
     vec::Vec,
 };
 
+use memmap2::Mmap;
+
+use crate::checkpoint::Bitmap;
+
 /// Represents a vertex in the graph.
 #[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Vertex(pub u32);
@@ -31,33 +36,580 @@ impl Route {
     }
 }
 
-/// Represents a cost matrix.
-pub struct CostMatrix {
+/// Magic bytes at the start of a cost matrix file.
+pub const COST_MATRIX_MAGIC: [u8; 8] = *b"SPCOSTMX";
+/// Version of the cost matrix file format.
+pub const COST_MATRIX_VERSION: u32 = 1;
+/// Length of the fixed part of the header, the parameters follow it.
+const FIXED_HEADER_LEN: usize = 56;
+/// The data of a cost matrix starts at a multiple of this.
+const HEADER_ALIGN: usize = 64;
+/// Parameter holding the rows that were computed, all rows were if it is missing.
+pub(crate) const VALID_ROWS_PARAM: &str = "valid_rows";
+
+/// Represents the type of the elements of a cost matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    /// Little-endian `u32`.
+    U32 = 1,
+    /// `u32` compressed in blocks of delta coded varints, see
+    /// [`CompressedCostMatrix`](crate::compressed::CompressedCostMatrix).
+    Varint = 2,
+}
+
+impl ElementType {
+    /// Size of a decoded element in bytes.
+    #[inline]
+    pub fn size(self) -> usize {
+        match self {
+            ElementType::U32 | ElementType::Varint => std::mem::size_of::<u32>(),
+        }
+    }
+}
+
+impl TryFrom<u32> for ElementType {
+    type Error = io::Error;
+
+    #[inline]
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(ElementType::U32),
+            2 => Ok(ElementType::Varint),
+            _ => Err(invalid_data("unknown element type")),
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// Locks a cost matrix file, `shared` by the matrices mapping it or exclusively by a writer.
+/// Fails with `WouldBlock` instead of waiting for a conflicting lock.
+pub(crate) fn lock(file: &File, shared: bool) -> Result<(), io::Error> {
+    let result = if shared {
+        file.try_lock_shared()
+    } else {
+        file.try_lock()
+    };
+    result.map_err(|e| match e {
+        TryLockError::WouldBlock => {
+            io::Error::new(io::ErrorKind::WouldBlock, "cost matrix file is in use")
+        }
+        TryLockError::Error(e) => e,
+    })
+}
+
+/// Returns the size in bytes of a raw `rows` x `cols` matrix, failing if it does not fit into
+/// memory.
+#[inline]
+fn matrix_len(rows: usize, cols: usize) -> Result<usize, io::Error> {
+    rows.checked_mul(cols)
+        .and_then(|len| len.checked_mul(std::mem::size_of::<u32>()))
+        .ok_or_else(|| invalid_data("cost matrix is too large"))
+}
+
+/// Represents the header of a cost matrix file.
+///
+/// All numbers are stored little-endian. The fixed part holds the magic bytes, the format
+/// version, the length of the header, rows and columns as `u64`, the element type, the value
+/// standing for infinity, the checksum of the source graph, the length of the parameters and a
+/// reserved field. The parameters follow as `key=value` lines, the matrix starts after padding
+/// the header to a multiple of 64 bytes and is stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub rows: usize,
+    pub cols: usize,
+    pub element: ElementType,
+    /// Value of unreachable targets.
+    pub infinity: u32,
+    /// Checksum of the graph the matrix was computed from, 0 if unknown.
+    pub graph: u64,
+    /// Parameters of the computation that created the matrix.
+    pub params: Vec<(String, String)>,
+}
+
+impl Header {
+    /// Constructs a new `Header` of a `rows` x `cols` matrix of `u32` without parameters.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            element: ElementType::U32,
+            infinity: u32::MAX,
+            graph: 0,
+            params: Vec::new(),
+        }
+    }
+
+    /// Adds a parameter of the computation.
+    pub fn with_param(mut self, key: &str, value: impl Display) -> Self {
+        self.params.push((key.to_owned(), value.to_string()));
+        self
+    }
+
+    /// Returns the value of the parameter `key`.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Records the rows that were computed. The bitmap is encoded with fixed width, so
+    /// replacing it keeps the length of the header.
+    pub fn with_valid_rows(mut self, valid: &Bitmap) -> Self {
+        let value = valid.to_hex();
+        match self
+            .params
+            .iter_mut()
+            .find(|(key, _)| key == VALID_ROWS_PARAM)
+        {
+            Some((_, old)) => *old = value,
+            None => self.params.push((VALID_ROWS_PARAM.to_owned(), value)),
+        }
+        self
+    }
+
+    /// Returns the rows that were computed, `None` if all of them were.
+    pub fn valid_rows(&self) -> Result<Option<Bitmap>, io::Error> {
+        self.param(VALID_ROWS_PARAM)
+            .map(|value| {
+                Bitmap::from_hex(self.rows, value)
+                    .ok_or_else(|| invalid_data("cost matrix has an invalid row bitmap"))
+            })
+            .transpose()
+    }
+
+    fn encode_params(&self) -> String {
+        self.params
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect()
+    }
+
+    /// Returns the length of the header, where the matrix starts.
+    pub fn len(&self) -> usize {
+        (FIXED_HEADER_LEN + self.encode_params().len()).next_multiple_of(HEADER_ALIGN)
+    }
+
+    /// Returns whether the header describes an empty matrix.
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0 || self.cols == 0
+    }
+
+    /// Returns the length of the whole file in bytes if the matrix is not compressed, failing if
+    /// the matrix is too large.
+    pub fn file_len(&self) -> Result<u64, io::Error> {
+        matrix_len(self.rows, self.cols)?
+            .checked_add(self.len())
+            .map(|len| len as u64)
+            .ok_or_else(|| invalid_data("cost matrix is too large"))
+    }
+
+    /// Encodes the header including its padding.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, io::Error> {
+        let params = self.encode_params();
+        if self
+            .params
+            .iter()
+            .any(|(key, value)| key.contains(['=', '\n']) || value.contains('\n'))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "parameters must not contain line breaks or `=` in keys",
+            ));
+        }
+        let len = self.len();
+        let mut bytes = Vec::with_capacity(len);
+        bytes.extend_from_slice(&COST_MATRIX_MAGIC);
+        bytes.extend_from_slice(&COST_MATRIX_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(len as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.rows as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.cols as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.element as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.infinity.to_le_bytes());
+        bytes.extend_from_slice(&self.graph.to_le_bytes());
+        bytes.extend_from_slice(&(params.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(params.as_bytes());
+        bytes.resize(len, 0);
+        Ok(bytes)
+    }
+
+    /// Reads the header at the start of `file`.
+    pub fn read(file: &File) -> Result<Self, io::Error> {
+        let mut fixed = [0u8; FIXED_HEADER_LEN];
+        file.read_exact_at(&mut fixed, 0)?;
+        let u32_at = |at: usize| u32::from_le_bytes(fixed[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(fixed[at..at + 8].try_into().unwrap());
+        if fixed[..8] != COST_MATRIX_MAGIC {
+            return Err(invalid_data("not a cost matrix file"));
+        }
+        if u32_at(8) != COST_MATRIX_VERSION {
+            return Err(invalid_data("unsupported cost matrix version"));
+        }
+        let file_len = file.metadata()?.len();
+        // the length is checked before allocating the parameters
+        let params_len = u64::from(u32_at(48));
+        if params_len > file_len.saturating_sub(FIXED_HEADER_LEN as u64) {
+            return Err(invalid_data("cost matrix file is truncated"));
+        }
+        let mut params = vec![0u8; params_len as usize];
+        file.read_exact_at(&mut params, FIXED_HEADER_LEN as u64)?;
+        let params = String::from_utf8(params)
+            .map_err(|_| invalid_data("cost matrix parameters are not UTF-8"))?;
+        let header = Self {
+            rows: u64_at(16) as usize,
+            cols: u64_at(24) as usize,
+            element: ElementType::try_from(u32_at(32))?,
+            infinity: u32_at(36),
+            graph: u64_at(40),
+            params: params
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+        };
+        if header.len() != u32_at(12) as usize {
+            return Err(invalid_data("cost matrix header has a wrong length"));
+        }
+        // compressed matrices check their row index when they are opened
+        if header.element == ElementType::U32 && file_len < header.file_len()? {
+            return Err(invalid_data("cost matrix file is truncated"));
+        }
+        Ok(header)
+    }
+
+    /// Fails unless the matrix is stored as raw `u32`.
+    #[inline]
+    fn check_raw(&self) -> Result<(), io::Error> {
+        if self.element != ElementType::U32 {
+            return Err(invalid_data(
+                "cost matrix is compressed, use CompressedCostMatrix",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Represents a writer of cost matrix files, whose rows can be written in any order and from
+/// several threads.
+///
+/// The file is locked exclusively, so a writer cannot be created while a [`CostMatrix`] maps
+/// the file and vice versa.
+pub struct CostMatrixWriter {
     inner: File,
-    size: usize,
+    header: Header,
+}
+
+impl CostMatrixWriter {
+    /// Creates a cost matrix file at `path` described by `header`, all entries are 0 until
+    /// written.
+    pub fn create(path: &Path, header: Header) -> Result<Self, io::Error> {
+        header.check_raw()?;
+        let file_len = header.file_len()?;
+        let inner = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        // truncating a mapped file would take the memory of the map away
+        lock(&inner, false)?;
+        inner.set_len(0)?;
+        inner.write_all_at(&header.to_bytes()?, 0)?;
+        inner.set_len(file_len)?;
+        Ok(Self { inner, header })
+    }
+
+    /// Opens an existing cost matrix file at `path` to continue writing it.
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        let inner = OpenOptions::new().read(true).write(true).open(path)?;
+        lock(&inner, false)?;
+        let header = Header::read(&inner)?;
+        header.check_raw()?;
+        Ok(Self { inner, header })
+    }
+
+    #[inline]
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    #[inline]
+    fn row_offset(&self, row: usize) -> Result<u64, io::Error> {
+        if row >= self.header.rows {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "row is out of the matrix",
+            ));
+        }
+        Ok((self.header.len() + row * self.header.cols * self.header.element.size()) as u64)
+    }
+
+    /// Writes the values of `row`.
+    pub fn write_row(&self, row: usize, values: &[u32]) -> Result<(), io::Error> {
+        if values.len() != self.header.cols {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "row has to have a value per column",
+            ));
+        }
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        self.inner.write_all_at(&bytes, self.row_offset(row)?)
+    }
+
+    /// Reads the values of `row` as written before.
+    pub fn read_row(&self, row: usize) -> Result<Vec<u32>, io::Error> {
+        let mut bytes = vec![0u8; self.header.cols * self.header.element.size()];
+        self.inner
+            .read_exact_at(&mut bytes, self.row_offset(row)?)?;
+        Ok(bytes
+            .chunks_exact(std::mem::size_of::<u32>())
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+
+    /// Records the rows in `valid` as computed in the header. The header has to record valid
+    /// rows already, so the matrix stays in place.
+    pub fn write_valid_rows(&mut self, valid: &Bitmap) -> Result<(), io::Error> {
+        if self.header.valid_rows()?.is_none() || valid.len() != self.header.rows {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "header has no bitmap of all rows",
+            ));
+        }
+        let header = self.header.clone().with_valid_rows(valid);
+        debug_assert_eq!(header.len(), self.header.len());
+        self.inner.write_all_at(&header.to_bytes()?, 0)?;
+        self.header = header;
+        Ok(())
+    }
+
+    /// Flushes the written rows to disk.
+    pub fn sync(&self) -> Result<(), io::Error> {
+        self.inner.sync_data()
+    }
+
+    /// Finishes writing and returns the matrix for reading.
+    pub fn finish(self) -> Result<CostMatrix, io::Error> {
+        self.sync()?;
+        CostMatrix::from_parts(self.inner, self.header)
+    }
+}
+
+/// Represents a cost matrix, mapped into memory.
+///
+/// The file holds a shared lock while it is mapped, so writers of this crate fail to change it.
+/// The lock is advisory, other programs must not change the file while it is mapped.
+pub struct CostMatrix {
+    map: Mmap,
+    /// File of the map, holding its lock.
+    _file: File,
+    /// Byte offset of the matrix in the file.
+    offset: usize,
+    rows: usize,
+    cols: usize,
+    /// Header of the file, `None` for legacy files that only hold the matrix.
+    header: Option<Header>,
+    /// Rows that were computed, `None` if all of them were.
+    valid: Option<Bitmap>,
 }
 
 impl CostMatrix {
-    /// Constructs a new `CostMatrix`.
+    /// Constructs a new `CostMatrix` from a legacy file without header.
     ///
     /// # Arguments
     ///
     /// * `path` - A `Path` to the file containing the cost matrix.
     /// * `size` - The size N of the matrix NxN.
     pub fn new(path: &Path, size: usize) -> Result<Self, io::Error> {
+        Self::map(File::open(path)?, None, size, size)
+    }
+
+    /// Opens a cost matrix file, reading its size from the header.
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+        let header = Header::read(&file)?;
+        Self::from_parts(file, header)
+    }
+
+    fn from_parts(file: File, header: Header) -> Result<Self, io::Error> {
+        header.check_raw()?;
+        let (rows, cols) = (header.rows, header.cols);
+        let valid = header.valid_rows()?;
+        let mut matrix = Self::map(file, Some(header), rows, cols)?;
+        matrix.valid = valid;
+        Ok(matrix)
+    }
+
+    fn map(
+        file: File,
+        header: Option<Header>,
+        rows: usize,
+        cols: usize,
+    ) -> Result<Self, io::Error> {
+        // the lock of a writer finishing the file is converted, so no other writer can take
+        // over in between
+        lock(&file, true)?;
+        // SAFETY: writers of this crate fail to lock the file until the map is dropped, other
+        // programs must not change it, the contract of the type
+        let map = unsafe { Mmap::map(&file)? };
+        let offset = header.as_ref().map_or(0, Header::len);
+        let len = matrix_len(rows, cols)?
+            .checked_add(offset)
+            .ok_or_else(|| invalid_data("cost matrix is too large"))?;
+        if map.len() < len {
+            return Err(invalid_data("cost matrix file is truncated"));
+        }
         Ok(Self {
-            inner: File::open(path)?,
-            size,
+            map,
+            offset,
+            rows,
+            cols,
+            _file: file,
+            header,
+            valid: None,
         })
     }
 
-    /// Gets the cost between two vertices.
+    /// Restricts the matrix to the rows in `valid`, the others were not computed.
+    pub fn with_rows(mut self, valid: Bitmap) -> Result<Self, io::Error> {
+        if valid.len() != self.rows {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "expected a bitmap of all rows",
+            ));
+        }
+        self.valid = Some(valid);
+        Ok(self)
+    }
+
+    /// Returns the header, `None` for legacy files.
+    #[inline]
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Returns the number of rows.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns.
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the whole matrix in row-major order.
+    #[inline]
+    fn data(&self) -> &[u32] {
+        let len = self.rows * self.cols * std::mem::size_of::<u32>();
+        let bytes = &self.map[self.offset..self.offset + len];
+        // SAFETY: the map is page aligned and headers are padded to a multiple of 64 bytes, so
+        // the matrix is aligned for u32, whose stored little-endian form matches the targets
+        // this crate supports
+        let (head, data, tail) = unsafe { bytes.align_to::<u32>() };
+        debug_assert!(head.is_empty() && tail.is_empty());
+        data
+    }
+
+    /// Returns whether the row of `source` was computed.
+    #[inline]
+    pub fn is_valid(&self, source: Vertex) -> bool {
+        source != UNDEFINED
+            && usize::from(source) < self.rows
+            && self
+                .valid
+                .as_ref()
+                .is_none_or(|valid| valid.get(usize::from(source)))
+    }
+
+    #[inline]
+    fn check_row(&self, source: Vertex) -> Result<usize, io::Error> {
+        if !self.is_valid(source) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("row of vertex {} was not computed", source.0),
+            ));
+        }
+        Ok(usize::from(source))
+    }
+
+    #[inline]
+    fn check_col(&self, target: Vertex) -> Result<usize, io::Error> {
+        if target == UNDEFINED || usize::from(target) >= self.cols {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("vertex {} is not a column of the matrix", target.0),
+            ));
+        }
+        Ok(usize::from(target))
+    }
+
+    /// Gets the cost between two vertices, `u32::MAX` if `target` is unreachable. Fails if the
+    /// row of `source` was not computed.
+    #[inline]
     pub fn get(&self, source: Vertex, target: Vertex) -> Result<u32, io::Error> {
-        let ref mut bytes = [0u8; std::mem::size_of::<u32>()];
-        let offset = usize::from(target) * std::mem::size_of::<u32>()
-            + usize::from(source) * self.size * std::mem::size_of::<u32>();
-        self.inner.read_exact_at(bytes, offset as u64)?;
-        Ok(u32::from_le_bytes(*bytes))
+        let (row, col) = (self.check_row(source)?, self.check_col(target)?);
+        Ok(self.data()[row * self.cols + col])
+    }
+
+    /// Returns the costs from `source` to every vertex.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row of `source` was not computed.
+    #[inline]
+    pub fn row(&self, source: Vertex) -> &[u32] {
+        let row = self.check_row(source).unwrap();
+        &self.data()[row * self.cols..(row + 1) * self.cols]
+    }
+
+    /// Returns the costs from every vertex to `target`. Entries of rows that were not computed
+    /// are meaningless, see [`CostMatrix::is_valid`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` is not a column of the matrix.
+    #[inline]
+    pub fn column(&self, target: Vertex) -> impl ExactSizeIterator<Item = u32> + '_ {
+        let col = self.check_col(target).unwrap();
+        let data = self.data();
+        (0..self.rows).map(move |row| data[row * self.cols + col])
+    }
+
+    /// Extracts the costs from `sources` to `targets` as a row-major matrix of
+    /// `sources.len()` x `targets.len()`.
+    pub fn submatrix(&self, sources: &[Vertex], targets: &[Vertex]) -> Result<Vec<u32>, io::Error> {
+        let cols = targets
+            .iter()
+            .map(|&target| self.check_col(target))
+            .collect::<Result<Vec<usize>, io::Error>>()?;
+        let mut sub = Vec::with_capacity(sources.len() * targets.len());
+        for &source in sources {
+            self.check_row(source)?;
+            let row = self.row(source);
+            sub.extend(cols.iter().map(|&col| row[col]));
+        }
+        Ok(sub)
+    }
+
+    /// Returns an iterator over the computed rows and their sources.
+    pub fn iter_rows(&self) -> impl Iterator<Item = (Vertex, &[u32])> + '_ {
+        self.data()
+            .chunks_exact(usize::max(1, self.cols))
+            .take(self.rows)
+            .enumerate()
+            .map(|(row, costs)| (Vertex::try_from(row).unwrap(), costs))
+            .filter(|&(source, _)| self.is_valid(source))
     }
 }
 
@@ -122,6 +674,15 @@ pub struct Edge {
     pub weight: u32,
 }
 
+/// Represents an edge with a cost and the amount of a second resource it consumes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DualEdge {
+    pub from: Vertex,
+    pub to: Vertex,
+    pub weight: u32,
+    pub resource: u32,
+}
+
 /// Enumerates different kinds of graph errors.
 #[non_exhaustive]
 #[derive(Debug, PartialEq, Eq)]
@@ -174,10 +735,71 @@ impl FromStr for Edge {
 /// Represents an error when parsing vertices.
 pub type ParseVertexError = ParseEdgeError;
 
+/// Represents a turn from the arc `from -> via` onto the arc `via -> to`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Turn {
+    pub from: Vertex,
+    pub via: Vertex,
+    pub to: Vertex,
+    /// The additional cost of taking the turn, `None` if the turn is forbidden.
+    pub cost: Option<u32>,
+}
+
+/// Represents an error when parsing turns.
+pub type ParseTurnError = ParseEdgeError;
+
+impl FromStr for Turn {
+    type Err = ParseTurnError;
+
+    /// Parses a turn cost row `t <from> <via> <to> <cost>` or a turn restriction row
+    /// `r <from> <via> <to>`.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split_whitespace();
+        let restricted = match fields.next() {
+            Some("t") => false,
+            Some("r") => true,
+            _ => {
+                return Err(ParseTurnError {
+                    kind: GraphErrorKind::NoDataRow,
+                    line: String::from(s),
+                })
+            }
+        };
+        let from = fields.next();
+        let via = fields.next();
+        let to = fields.next();
+        let cost = if restricted { Some("0") } else { fields.next() };
+        match (from, via, to, cost) {
+            (Some(f), Some(v), Some(t), Some(c)) => match (
+                Vertex::from_str(f),
+                Vertex::from_str(v),
+                Vertex::from_str(t),
+                u32::from_str(c),
+            ) {
+                (Ok(from), Ok(via), Ok(to), Ok(cost)) => Ok(Turn {
+                    from,
+                    via,
+                    to,
+                    cost: (!restricted).then_some(cost),
+                }),
+                _ => Err(ParseTurnError {
+                    kind: GraphErrorKind::InvalidValue,
+                    line: String::from(s),
+                }),
+            },
+            _ => Err(ParseTurnError {
+                kind: GraphErrorKind::InvalidFormat,
+                line: String::from(s),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Coordinates {
-    x: i64,
-    y: i64,
+    pub x: i64,
+    pub y: i64,
 }
 
 /// Represents coordinates.
@@ -249,6 +871,50 @@ pub fn load_edges(path: &Path) -> impl Iterator<Item = Edge> {
         })
 }
 
+/// Loads edges weighted with the metric in `cost` that consume the metric in `resource`, e.g.
+/// the `-t.gr` and `-d.gr` files of a region for fastest routes under a distance budget.
+#[inline]
+pub fn load_dual_edges(cost: &Path, resource: &Path) -> impl Iterator<Item = DualEdge> {
+    load_edges(cost).zip(load_edges(resource)).map(|(c, r)| {
+        if c.from != r.from || c.to != r.to {
+            panic!("metrics disagree on arc {:?} and {:?}", c, r)
+        }
+        DualEdge {
+            from: c.from,
+            to: c.to,
+            weight: c.weight,
+            resource: r.weight,
+        }
+    })
+}
+
+/// Loads turn costs and restrictions from a file with rows `t <from> <via> <to> <cost>` and
+/// `r <from> <via> <to>`. All other rows are treated like comments.
+#[inline]
+pub fn load_turns(path: &Path) -> impl Iterator<Item = Turn> {
+    let display = path.display();
+    // Open the path in read-only mode, returns `io::Result<File>`
+    let file = match File::open(path) {
+        Err(why) => panic!("couldn't open {}: {}", display, why),
+        Ok(file) => file,
+    };
+    BufReader::new(file)
+        .lines()
+        .filter_map(|line| match Turn::from_str(&line.ok()?) {
+            Ok(t) => Some(t),
+            Err(err) => {
+                if err.kind != GraphErrorKind::NoDataRow {
+                    panic!(
+                        "couldn't parse line:\n{}\nbecause of: {:#?}",
+                        err.line, err.kind
+                    )
+                } else {
+                    None
+                }
+            }
+        })
+}
+
 /// Loads coordinates from a file downloaded from https://www.diag.uniroma1.it/challenge9/download.shtml.
 #[inline]
 pub fn load_coordinates(path: &Path) -> impl Iterator<Item = Coordinates> {
@@ -306,12 +972,40 @@ pub fn load_max_vertex(path: &Path) -> Vertex {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use std::collections::HashSet;
     use std::{fs::File, os::unix::fs::FileExt, path::Path};
 
-    use crate::dimacs::Vertex;
+    use rand::{thread_rng, Rng};
+
+    use crate::dimacs::{Edge, Vertex};
 
-    use super::CostMatrix;
+    use super::{Bitmap, CostMatrix, CostMatrixWriter, Header};
+
+    /// Generates a strongly connected graph with `n` vertices: a cycle through all vertices
+    /// plus up to `m` random arcs, without loops or parallel arcs.
+    pub(crate) fn random_edges(n: usize, m: usize) -> Vec<Edge> {
+        let mut rng = thread_rng();
+        let n = n as u32;
+        let cycle = (1..=n).map(|v| Edge {
+            from: Vertex(v),
+            to: Vertex(v % n + 1),
+            weight: rng.gen_range(1..1000),
+        });
+        let mut edges: Vec<Edge> = cycle.collect();
+        let mut arcs: HashSet<(Vertex, Vertex)> = edges.iter().map(|e| (e.from, e.to)).collect();
+        for _ in 0..m {
+            let (from, to) = (Vertex(rng.gen_range(1..=n)), Vertex(rng.gen_range(1..=n)));
+            if from != to && arcs.insert((from, to)) {
+                edges.push(Edge {
+                    from,
+                    to,
+                    weight: rng.gen_range(1..1000),
+                });
+            }
+        }
+        edges
+    }
 
     #[test]
     fn cost_matrix_test() {
@@ -333,4 +1027,98 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn cost_matrix_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("matrix.cost");
+        let header = Header {
+            graph: 0xabc,
+            ..Header::new(2, 3).with_param("algorithm", "test")
+        };
+        let writer = CostMatrixWriter::create(&path, header.clone()).unwrap();
+        writer.write_row(1, &[4, 5, u32::MAX]).unwrap();
+        writer.write_row(0, &[1, 2, 3]).unwrap();
+        assert!(writer.write_row(2, &[0, 0, 0]).is_err());
+        assert!(writer.write_row(0, &[0, 0]).is_err());
+        assert_eq!(writer.read_row(1).unwrap(), vec![4, 5, u32::MAX]);
+        drop(writer);
+
+        let cost = CostMatrix::open(&path).unwrap();
+        assert_eq!(cost.header(), Some(&header));
+        assert_eq!(cost.header().unwrap().param("algorithm"), Some("test"));
+        assert_eq!((cost.rows(), cost.cols()), (2, 3));
+        assert_eq!(cost.get(Vertex(1), Vertex(3)).unwrap(), 3);
+        assert_eq!(cost.get(Vertex(2), Vertex(3)).unwrap(), u32::MAX);
+        assert!(cost.get(Vertex(3), Vertex(1)).is_err());
+        assert!(cost.get(Vertex(1), Vertex(4)).is_err());
+
+        // sizes written by other tools must not overflow or allocate what the file lacks
+        for (at, bytes) in [
+            (16, u64::MAX.to_le_bytes().to_vec()),
+            (24, (1u64 << 62).to_le_bytes().to_vec()),
+            (48, u32::MAX.to_le_bytes().to_vec()),
+        ] {
+            let damaged = dir.path().join("damaged.cost");
+            std::fs::copy(&path, &damaged).unwrap();
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .open(&damaged)
+                .unwrap();
+            file.write_all_at(&bytes, at).unwrap();
+            assert!(CostMatrix::open(&damaged).is_err());
+        }
+        assert!(CostMatrix::new(&path, usize::MAX).is_err());
+        assert!(Header::new(usize::MAX, 2).file_len().is_err());
+
+        // files that are no cost matrices or were cut off are rejected
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(header.file_len().unwrap() - 1).unwrap();
+        assert!(CostMatrix::open(&path).is_err());
+        file.write_all_at(b"NOTCOSTS", 0).unwrap();
+        assert!(CostMatrix::open(&path).is_err());
+    }
+
+    #[test]
+    fn cost_matrix_access() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("matrix.cost");
+        let writer = CostMatrixWriter::create(&path, Header::new(3, 4)).unwrap();
+        for row in 0..3 {
+            let values: Vec<u32> = (0..4).map(|col| 10 * row + col).collect();
+            writer.write_row(row as usize, &values).unwrap();
+        }
+        let cost = writer.finish().unwrap();
+        assert_eq!(cost.row(Vertex(2)), &[10, 11, 12, 13]);
+        assert_eq!(cost.column(Vertex(3)).collect::<Vec<_>>(), vec![2, 12, 22]);
+        let sub = cost
+            .submatrix(&[Vertex(3), Vertex(1)], &[Vertex(4), Vertex(2), Vertex(4)])
+            .unwrap();
+        assert_eq!(sub, vec![23, 21, 23, 3, 1, 3]);
+        assert!(cost.submatrix(&[Vertex(4)], &[Vertex(1)]).is_err());
+        assert!(cost.submatrix(&[Vertex(1)], &[Vertex(5)]).is_err());
+        assert_eq!(cost.iter_rows().count(), 3);
+
+        // rows that were not computed are skipped and fail
+        let mut valid = Bitmap::new(3);
+        valid.set(0, true);
+        valid.set(2, true);
+        let cost = cost.with_rows(valid).unwrap();
+        let sources: Vec<Vertex> = cost.iter_rows().map(|(source, _)| source).collect();
+        assert_eq!(sources, vec![Vertex(1), Vertex(3)]);
+        assert!(cost.submatrix(&[Vertex(2)], &[Vertex(1)]).is_err());
+        assert!(cost.get(Vertex(2), Vertex(1)).is_err());
+
+        // the file cannot be written while it is mapped
+        let reopened = CostMatrix::open(&path).unwrap();
+        for result in [
+            CostMatrixWriter::open(&path),
+            CostMatrixWriter::create(&path, Header::new(1, 1)),
+        ] {
+            assert_eq!(result.err().unwrap().kind(), std::io::ErrorKind::WouldBlock);
+        }
+        assert_eq!(reopened.row(Vertex(3)), &[20, 21, 22, 23]);
+        drop((cost, reopened));
+        assert!(CostMatrixWriter::open(&path).is_ok());
+    }
 }
@@ -0,0 +1,352 @@
+use std::collections::BinaryHeap;
+
+use crate::dijkstra::{sssp, DicirectionalList, Dijkstra, Graph, InitDijkstra, Item, NeighborList};
+use crate::dimacs::{Edge, Route, Vertex, UNDEFINED};
+
+/// Represents the result of a single-source shortest path computation.
+pub struct ShortestPathTree {
+    source: Vertex,
+    /// Distance of every vertex from the source, `u32::MAX` if it is unreachable.
+    dist: Vec<u32>,
+    /// Predecessor of every vertex in the tree, `UNDEFINED` if it is unreachable.
+    pred: Vec<Vertex>,
+}
+
+impl ShortestPathTree {
+    /// Collects the tree of `n` vertices computed by a finished search from `source`.
+    pub fn new(source: Vertex, search: &impl Dijkstra, n: usize) -> Self {
+        let (dist, pred) = (0..n)
+            .map(|i| {
+                let v = Vertex::try_from(i).unwrap();
+                match search.get_meta(v) {
+                    Some((dist, prev)) => (dist.into(), prev.into()),
+                    None => (u32::MAX, UNDEFINED),
+                }
+            })
+            .unzip();
+        Self { source, dist, pred }
+    }
+
+    /// Builds a tree from the distance and predecessor of every vertex, using `u32::MAX` and
+    /// `UNDEFINED` for unreachable vertices.
+    pub fn from_parts(source: Vertex, dist: Vec<u32>, pred: Vec<Vertex>) -> Self {
+        debug_assert_eq!(dist.len(), pred.len());
+        Self { source, dist, pred }
+    }
+
+    #[inline]
+    pub fn source(&self) -> Vertex {
+        self.source
+    }
+
+    #[inline]
+    pub fn get_dist(&self, target: Vertex) -> Option<u32> {
+        let dist = self.dist[usize::from(target)];
+        (dist != u32::MAX).then_some(dist)
+    }
+
+    #[inline]
+    pub fn get_pred(&self, target: Vertex) -> Option<Vertex> {
+        let pred = self.pred[usize::from(target)];
+        (pred != UNDEFINED).then_some(pred)
+    }
+
+    pub fn get_path(&self, target: Vertex) -> Option<Route> {
+        let mut path = Vec::new();
+        let mut head = target;
+        while let Some(prev) = self.get_pred(head) {
+            path.push(head);
+            if head == self.source {
+                return Some(Route(path));
+            }
+            head = prev;
+        }
+        None
+    }
+}
+
+/// Represents a shortest path tree that is repaired after edge weight changes.
+///
+/// After a batch of changes only the vertices whose distance may change are touched: the
+/// subtrees hanging off increased tree edges are detached and reattached to the rest of the
+/// tree, then decreases and reattachments are propagated by a Dijkstra seeded with just the
+/// changed vertices, as proposed by Ramalingam and Reps.
+pub struct DynamicSssp {
+    tree: ShortestPathTree,
+    /// Marks vertices touched by the current update, valid while equal to `round`.
+    marks: Vec<u32>,
+    round: u32,
+}
+
+impl DynamicSssp {
+    /// Computes the initial tree from `source` with the priority queue `Q`.
+    pub fn new<Q: InitDijkstra>(source: Vertex, graph: &DicirectionalList<NeighborList>) -> Self {
+        let n = graph.forward.len();
+        let search = sssp(Q::init_dijkstra(source, n), &graph.forward);
+        Self {
+            tree: ShortestPathTree::new(source, &search, n),
+            marks: vec![0; n],
+            round: 0,
+        }
+    }
+
+    #[inline]
+    pub fn tree(&self) -> &ShortestPathTree {
+        &self.tree
+    }
+
+    /// Applies a batch of weight changes to `graph` and repairs the tree.
+    ///
+    /// Each change sets the weight of the arc `from -> to`, changes to arcs that do not exist
+    /// are ignored. Returns the number of vertices whose distance had to be reconsidered.
+    pub fn update(
+        &mut self,
+        graph: &mut DicirectionalList<NeighborList>,
+        changes: &[Edge],
+    ) -> usize {
+        self.round += 1;
+        if self.round == u32::MAX {
+            self.marks.fill(0);
+            self.round = 1;
+        }
+        // apply the batch, comparing the final weight of every arc with its weight before
+        let mut applied: Vec<(Edge, u32)> = Vec::with_capacity(changes.len());
+        for change in changes {
+            if let Some(old) = graph.set_weight(change.from, change.to, change.weight) {
+                applied.push((change.clone(), old));
+            }
+        }
+        applied.sort_by_key(|(e, _)| (e.from.0, e.to.0));
+        applied.dedup_by(|(later, _), (first, _)| {
+            let same = later.from == first.from && later.to == first.to;
+            if same {
+                first.weight = later.weight;
+            }
+            same
+        });
+
+        let mut touched = 0;
+        let mut detached = Vec::new();
+        let mut decreased = Vec::new();
+        for (change, old) in applied.iter() {
+            let old = *old;
+            let (from, to) = (usize::from(change.from), usize::from(change.to));
+            if change.weight > old {
+                let in_tree = self.tree.pred[to] == change.from
+                    && self.tree.dist[from].saturating_add(old) == self.tree.dist[to];
+                if in_tree && self.marks[to] != self.round {
+                    self.marks[to] = self.round;
+                    detached.push(change.to);
+                }
+            } else if change.weight < old {
+                decreased.push(change);
+            }
+        }
+
+        // detach the subtrees below increased tree edges
+        let mut next = 0;
+        while next < detached.len() {
+            let u = detached[next];
+            next += 1;
+            for e in graph.forward.get_neighbors(u) {
+                let v = usize::from(e.to);
+                if self.tree.pred[v] == u && self.marks[v] != self.round {
+                    self.marks[v] = self.round;
+                    detached.push(e.to);
+                }
+            }
+        }
+        for &v in detached.iter() {
+            self.tree.dist[usize::from(v)] = u32::MAX;
+            self.tree.pred[usize::from(v)] = UNDEFINED;
+        }
+        touched += detached.len();
+
+        // reattach them to the intact part of the tree
+        let mut queue = BinaryHeap::new();
+        for &v in detached.iter() {
+            let (mut best, mut pred) = (u32::MAX, UNDEFINED);
+            for e in graph.backward.get_neighbors(v) {
+                let u = usize::from(e.to);
+                if self.marks[u] == self.round {
+                    continue;
+                }
+                let alt = self.tree.dist[u].saturating_add(e.weight);
+                if alt < best {
+                    (best, pred) = (alt, e.to);
+                }
+            }
+            if best != u32::MAX {
+                self.tree.dist[usize::from(v)] = best;
+                self.tree.pred[usize::from(v)] = pred;
+                queue.push(Item {
+                    key: best,
+                    value: v,
+                });
+            }
+        }
+        for change in decreased {
+            let alt = self.tree.dist[usize::from(change.from)].saturating_add(change.weight);
+            let to = usize::from(change.to);
+            if alt < self.tree.dist[to] {
+                if self.marks[to] != self.round {
+                    self.marks[to] = self.round;
+                    touched += 1;
+                }
+                self.tree.dist[to] = alt;
+                self.tree.pred[to] = change.from;
+                queue.push(Item {
+                    key: alt,
+                    value: change.to,
+                });
+            }
+        }
+
+        // propagate the changes
+        while let Some(Item { key, value: u }) = queue.pop() {
+            if key > self.tree.dist[usize::from(u)] {
+                continue;
+            }
+            for e in graph.forward.get_neighbors(u) {
+                let v = usize::from(e.to);
+                let alt = key.saturating_add(e.weight);
+                if alt < self.tree.dist[v] {
+                    if self.marks[v] != self.round {
+                        self.marks[v] = self.round;
+                        touched += 1;
+                    }
+                    self.tree.dist[v] = alt;
+                    self.tree.pred[v] = u;
+                    queue.push(Item {
+                        key: alt,
+                        value: e.to,
+                    });
+                }
+            }
+        }
+        touched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::OwnedLookup;
+    use crate::dimacs::tests::random_edges;
+    use crate::implicit_heaps::BinaryHeap;
+
+    /// Checks the tree against a search from scratch on the current graph.
+    fn assert_tree(tree: &ShortestPathTree, graph: &DicirectionalList<NeighborList>) {
+        let n = graph.forward.len();
+        let source: OwnedLookup<BinaryHeap> = OwnedLookup::from((tree.source(), n));
+        let result = sssp(source, &graph.forward);
+        for i in 0..n {
+            let v = Vertex::try_from(i).unwrap();
+            assert_eq!(tree.get_dist(v), result.get_dist(v), "distance of {}", v);
+            if let (Some(dist), Some(pred)) = (tree.get_dist(v), tree.get_pred(v)) {
+                if v != tree.source() {
+                    let weight = graph
+                        .forward
+                        .get_neighbors(pred)
+                        .filter(|e| e.to == v)
+                        .map(|e| e.weight)
+                        .min()
+                        .unwrap();
+                    assert_eq!(tree.get_dist(pred).unwrap() + weight, dist);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn repair_batches() {
+        let n = 500;
+        let edges = random_edges(n, 3 * n);
+        let mut graph: DicirectionalList<NeighborList> =
+            DicirectionalList::new(n, edges.clone().into_iter());
+        let mut dynamic = DynamicSssp::new::<BinaryHeap>(Vertex(1), &graph);
+        assert_tree(dynamic.tree(), &graph);
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            let changes: Vec<Edge> = (0..rng.gen_range(1..20))
+                .map(|_| {
+                    let e = &edges[rng.gen_range(0..edges.len())];
+                    Edge {
+                        from: e.from,
+                        to: e.to,
+                        weight: rng.gen_range(1..2000),
+                    }
+                })
+                .collect();
+            dynamic.update(&mut graph, &changes);
+            assert_tree(dynamic.tree(), &graph);
+        }
+    }
+
+    #[test]
+    fn repair_is_local() {
+        let n = 500;
+        let edges = random_edges(n, 3 * n);
+        let mut graph: DicirectionalList<NeighborList> =
+            DicirectionalList::new(n, edges.clone().into_iter());
+        let mut dynamic = DynamicSssp::new::<BinaryHeap>(Vertex(1), &graph);
+        // increasing an arc outside of the tree changes nothing
+        let unused = edges
+            .iter()
+            .find(|e| dynamic.tree().get_pred(e.to) != Some(e.from))
+            .unwrap();
+        let change = Edge {
+            from: unused.from,
+            to: unused.to,
+            weight: unused.weight + 1000,
+        };
+        assert_eq!(dynamic.update(&mut graph, &[change]), 0);
+        assert_tree(dynamic.tree(), &graph);
+        // closing the arc into a leaf only touches the leaf
+        let leaf = (2..=n as u32)
+            .map(Vertex)
+            .find(|&v| {
+                graph
+                    .forward
+                    .get_neighbors(v)
+                    .all(|e| dynamic.tree().get_pred(e.to) != Some(v))
+            })
+            .unwrap();
+        let pred = dynamic.tree().get_pred(leaf).unwrap();
+        let change = Edge {
+            from: pred,
+            to: leaf,
+            weight: 1_000_000,
+        };
+        assert_eq!(dynamic.update(&mut graph, &[change]), 1);
+        assert_tree(dynamic.tree(), &graph);
+    }
+
+    #[test]
+    fn repeated_changes() {
+        let n = 500;
+        let edges = random_edges(n, 3 * n);
+        let mut graph: DicirectionalList<NeighborList> =
+            DicirectionalList::new(n, edges.clone().into_iter());
+        let mut dynamic = DynamicSssp::new::<BinaryHeap>(Vertex(1), &graph);
+        // the last change of an arc counts, compared to its weight before the batch
+        let leaf = Vertex(n as u32);
+        let pred = dynamic.tree().get_pred(leaf).unwrap();
+        let changes = [1, 1_000_000].map(|weight| Edge {
+            from: pred,
+            to: leaf,
+            weight,
+        });
+        dynamic.update(&mut graph, &changes);
+        assert_tree(dynamic.tree(), &graph);
+        let changes = [1_000_000, 2_000_000, 1].map(|weight| Edge {
+            from: pred,
+            to: leaf,
+            weight,
+        });
+        dynamic.update(&mut graph, &changes);
+        assert_tree(dynamic.tree(), &graph);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::dijkstra::{
+    assert_send, Backend, DecreaseKey, Handle, InitDijkstra, PriorityQueue, Search,
+};
+use crate::dimacs::Vertex;
+
+/// Marks a missing link.
+const NIL: u32 = u32::MAX;
+
+/// Represents a node in the Fibonacci heap.
+#[derive(Debug)]
+struct Node {
+    key: u32,
+    value: Vertex,
+    parent: u32,
+    /// Any child, the children form a circular list.
+    child: u32,
+    /// Neighbors in the circular list of roots or siblings.
+    left: u32,
+    right: u32,
+    degree: u32,
+    /// Whether the node lost a child since it became a child itself.
+    marked: bool,
+}
+
+/// Represents a Fibonacci heap whose nodes are stored in an arena and referenced by index.
+///
+/// Nodes are never reused until the heap is reset, so handles of popped items stay unique.
+pub struct FibonacciHeap {
+    nodes: Vec<Node>,
+    /// The root with the minimum key, any node of the circular root list.
+    min: u32,
+    len: usize,
+    /// Root of each degree during consolidation.
+    degrees: Vec<u32>,
+}
+
+impl From<Vertex> for FibonacciHeap {
+    #[inline]
+    fn from(value: Vertex) -> Self {
+        let mut heap = Self {
+            nodes: Vec::new(),
+            min: NIL,
+            len: 0,
+            degrees: Vec::new(),
+        };
+        heap.push(0, value);
+        heap
+    }
+}
+
+impl FibonacciHeap {
+    #[inline]
+    fn node(&self, id: u32) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    #[inline]
+    fn node_mut(&mut self, id: u32) -> &mut Node {
+        &mut self.nodes[id as usize]
+    }
+
+    /// Removes a node from its circular list, leaving it as a list of its own.
+    #[inline]
+    fn unlink(&mut self, id: u32) {
+        let (left, right) = (self.node(id).left, self.node(id).right);
+        self.node_mut(left).right = right;
+        self.node_mut(right).left = left;
+        let node = self.node_mut(id);
+        node.left = id;
+        node.right = id;
+    }
+
+    /// Inserts a single node into the circular list after `at`.
+    #[inline]
+    fn splice(&mut self, at: u32, id: u32) {
+        let right = self.node(at).right;
+        self.node_mut(id).left = at;
+        self.node_mut(id).right = right;
+        self.node_mut(right).left = id;
+        self.node_mut(at).right = id;
+    }
+
+    /// Adds a single node to the root list.
+    #[inline]
+    fn add_root(&mut self, id: u32) {
+        self.node_mut(id).parent = NIL;
+        if self.min == NIL {
+            self.min = id;
+        } else {
+            self.splice(self.min, id);
+            if self.node(id).key < self.node(self.min).key {
+                self.min = id;
+            }
+        }
+    }
+
+    /// Makes the root `child` a child of the root `parent`.
+    #[inline]
+    fn link(&mut self, child: u32, parent: u32) {
+        self.unlink(child);
+        match self.node(parent).child {
+            NIL => self.node_mut(parent).child = child,
+            first => self.splice(first, child),
+        }
+        let node = self.node_mut(child);
+        node.parent = parent;
+        node.marked = false;
+        self.node_mut(parent).degree += 1;
+    }
+
+    /// Collects a circular list starting at `first`.
+    fn list(&self, first: u32) -> Vec<u32> {
+        let mut list = Vec::new();
+        if first == NIL {
+            return list;
+        }
+        let mut id = first;
+        loop {
+            list.push(id);
+            id = self.node(id).right;
+            if id == first {
+                return list;
+            }
+        }
+    }
+
+    /// Links roots of equal degree until all roots have distinct degrees.
+    fn consolidate(&mut self) {
+        for root in self.list(self.min) {
+            let mut x = root;
+            let mut degree = self.node(x).degree as usize;
+            loop {
+                if degree >= self.degrees.len() {
+                    self.degrees.resize(degree + 1, NIL);
+                }
+                let y = self.degrees[degree];
+                if y == NIL {
+                    break;
+                }
+                self.degrees[degree] = NIL;
+                if self.node(y).key < self.node(x).key {
+                    self.link(x, y);
+                    x = y;
+                } else {
+                    self.link(y, x);
+                }
+                degree += 1;
+            }
+            self.degrees[degree] = x;
+        }
+        self.min = NIL;
+        for degree in 0..self.degrees.len() {
+            let root = std::mem::replace(&mut self.degrees[degree], NIL);
+            if root != NIL && (self.min == NIL || self.node(root).key < self.node(self.min).key) {
+                self.min = root;
+            }
+        }
+    }
+
+    /// Moves `id` from the children of `parent` to the roots.
+    #[inline]
+    fn cut(&mut self, id: u32, parent: u32) {
+        if self.node(parent).child == id {
+            let right = self.node(id).right;
+            self.node_mut(parent).child = if right == id { NIL } else { right };
+        }
+        self.unlink(id);
+        self.node_mut(parent).degree -= 1;
+        self.node_mut(id).marked = false;
+        self.add_root(id);
+    }
+}
+
+impl PriorityQueue for FibonacciHeap {
+    type RefType = Handle;
+    type Key = u32;
+    type Value = Vertex;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
+        if self.min == NIL {
+            return None;
+        }
+        let min = self.min;
+        for child in self.list(self.node(min).child) {
+            self.unlink(child);
+            self.add_root(child);
+        }
+        self.node_mut(min).child = NIL;
+        let right = self.node(min).right;
+        self.unlink(min);
+        if right == min {
+            self.min = NIL;
+        } else {
+            self.min = right;
+            self.consolidate();
+        }
+        self.len -= 1;
+        let node = self.node(min);
+        Some((node.key, node.value))
+    }
+
+    #[inline]
+    fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
+        let id = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            key,
+            value,
+            parent: NIL,
+            child: NIL,
+            left: id,
+            right: id,
+            degree: 0,
+            marked: false,
+        });
+        self.add_root(id);
+        self.len += 1;
+        Handle(id)
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        self.nodes.clear();
+        self.min = NIL;
+        self.len = 0;
+        self.push(0, source);
+    }
+}
+
+impl DecreaseKey for FibonacciHeap {
+    #[inline]
+    fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
+        let id = of.0;
+        self.node_mut(id).key = key;
+        let parent = self.node(id).parent;
+        if parent != NIL && key < self.node(parent).key {
+            self.cut(id, parent);
+            // cascading cut of the ancestors that already lost a child
+            let mut node = parent;
+            while self.node(node).parent != NIL {
+                if !self.node(node).marked {
+                    self.node_mut(node).marked = true;
+                    break;
+                }
+                let parent = self.node(node).parent;
+                self.cut(node, parent);
+                node = parent;
+            }
+        }
+        if key < self.node(self.min).key {
+            self.min = id;
+        }
+    }
+}
+
+impl InitDijkstra for FibonacciHeap {
+    type Data<B: Backend> = Search<Self, B>;
+}
+
+const _: () = assert_send::<FibonacciHeap>();
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{Dijkstra, Neighbor};
+
+    #[test]
+    fn simple_merge() {
+        let mut heap = FibonacciHeap::from(Vertex(1));
+        heap.push(2, Vertex(2));
+        let this = heap.push(4, Vertex(3));
+        assert_eq!(heap.pop(), Some((0, Vertex(1))));
+        heap.decrease_key(this, 1);
+        assert_eq!(heap.pop(), Some((1, Vertex(3))));
+        assert_eq!(heap.pop(), Some((2, Vertex(2))));
+        assert_eq!(heap.pop(), None);
+        heap.reset(Vertex(9));
+        assert_eq!(heap.pop(), Some((0, Vertex(9))));
+    }
+
+    #[test]
+    fn push_pop_fibonacci_heap() {
+        let n = 10000;
+        let mut highest_min = 0;
+        let mut dijkstra: Search<FibonacciHeap> = Search::from((Vertex(1), n));
+        let mut rng = thread_rng();
+        //push
+        for i in 1..n {
+            let to = Vertex::try_from(i).unwrap();
+            dijkstra.explore(
+                Vertex(1),
+                0,
+                &Neighbor {
+                    weight: rng.gen_range(1..1000000),
+                    to,
+                },
+            );
+        }
+        //pop some to build up trees that decrease_key has to cut
+        let mut popped = Vec::new();
+        for _ in 0..n / 10 {
+            let (key, value) = dijkstra.pop_min().unwrap();
+            assert!(key >= highest_min);
+            highest_min = key;
+            popped.push(value);
+        }
+        //decrease_key
+        for _ in 0..n {
+            let to: Vertex = rng.gen_range(1..n).try_into().unwrap();
+            if popped.contains(&to) {
+                continue;
+            }
+            let (_, key, _) = dijkstra.meta.get(&to).unwrap();
+            let key = u32::max(key / 2, highest_min);
+            dijkstra.explore(Vertex(1), 0, &Neighbor { weight: key, to });
+        }
+        //pop
+        for _ in n / 10..n {
+            let (key, popped) = dijkstra.pop_min().unwrap();
+            let (_, stored_key, _) = dijkstra.meta.get(&popped).unwrap();
+            assert_eq!(key, *stored_key);
+            assert!(key >= highest_min);
+            highest_min = u32::max(highest_min, key);
+        }
+        assert_eq!(None, dijkstra.pop_min());
+    }
+}
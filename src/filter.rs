@@ -0,0 +1,401 @@
+use crate::dijkstra::{DicirectionalList, Graph, Neighbor};
+use crate::dimacs::{Coordinates, Vertex};
+
+/// Represents a fixed size set of indices stored as bits.
+#[derive(Clone, Debug)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// Constructs an empty set for indices below `len`.
+    #[inline]
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    #[inline]
+    pub fn insert(&mut self, index: usize) {
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    #[inline]
+    pub fn remove(&mut self, index: usize) {
+        self.words[index / 64] &= !(1 << (index % 64));
+    }
+
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+}
+
+/// Represents a view of a graph in which some vertices and arcs are blocked.
+///
+/// Searches on the view skip blocked vertices and arcs without copying the graph.
+/// Arcs are identified by their position when enumerating the neighbors of all vertices in
+/// order, see [`FilteredGraph::arc_id`].
+pub struct FilteredGraph<'a, G: Graph> {
+    graph: &'a G,
+    /// Id of the first arc of every vertex, followed by the number of arcs.
+    offsets: Vec<usize>,
+    vertices: BitSet,
+    arcs: BitSet,
+}
+
+impl<'a, G: Graph> FilteredGraph<'a, G> {
+    /// Constructs a view of `graph` with nothing blocked.
+    pub fn new(graph: &'a G) -> Self {
+        let n = graph.num_vertices();
+        let mut offsets = Vec::with_capacity(n + 1);
+        let mut m = 0;
+        for u in 0..n {
+            offsets.push(m);
+            m += graph.get_neighbors(Vertex::try_from(u).unwrap()).count();
+        }
+        offsets.push(m);
+        Self {
+            graph,
+            offsets,
+            vertices: BitSet::new(n),
+            arcs: BitSet::new(m),
+        }
+    }
+
+    /// Number of arcs in the underlying graph.
+    #[inline]
+    pub fn num_arcs(&self) -> usize {
+        self.offsets[self.offsets.len() - 1]
+    }
+
+    /// Gets the id of the first arc `from -> to` that is not blocked yet.
+    pub fn arc_id(&self, from: Vertex, to: Vertex) -> Option<usize> {
+        self.find_arc(from, |e| e.to == to)
+    }
+
+    /// Gets the id of the first arc `from -> to` of weight `weight` that is not blocked yet,
+    /// which tells parallel arcs apart.
+    pub fn arc_id_with_weight(&self, from: Vertex, to: Vertex, weight: u32) -> Option<usize> {
+        self.find_arc(from, |e| e.to == to && e.weight == weight)
+    }
+
+    fn find_arc<P>(&self, from: Vertex, predicate: P) -> Option<usize>
+    where
+        P: Fn(&Neighbor) -> bool,
+    {
+        let first = self.offsets[usize::from(from)];
+        self.graph
+            .get_neighbors(from)
+            .enumerate()
+            .map(|(i, e)| (first + i, e))
+            .find(|(id, e)| predicate(e) && !self.arcs.contains(*id))
+            .map(|(id, _)| id)
+    }
+
+    /// Gets the tail of an arc and the arc itself.
+    fn neighbor(&self, id: usize) -> (Vertex, &Neighbor) {
+        let from = self.offsets.partition_point(|&first| first <= id) - 1;
+        let from_vertex = Vertex::try_from(from).unwrap();
+        let e = self
+            .graph
+            .get_neighbors(from_vertex)
+            .nth(id - self.offsets[from])
+            .unwrap();
+        (from_vertex, e)
+    }
+
+    /// Gets the endpoints of an arc.
+    pub fn arc(&self, id: usize) -> (Vertex, Vertex) {
+        let (from, e) = self.neighbor(id);
+        (from, e.to)
+    }
+
+    #[inline]
+    pub fn block_vertex(&mut self, v: Vertex) {
+        self.vertices.insert(usize::from(v));
+    }
+
+    #[inline]
+    pub fn unblock_vertex(&mut self, v: Vertex) {
+        self.vertices.remove(usize::from(v));
+    }
+
+    #[inline]
+    pub fn is_blocked(&self, v: Vertex) -> bool {
+        self.vertices.contains(usize::from(v))
+    }
+
+    #[inline]
+    pub fn block_arc(&mut self, id: usize) {
+        self.arcs.insert(id);
+    }
+
+    #[inline]
+    pub fn unblock_arc(&mut self, id: usize) {
+        self.arcs.remove(id);
+    }
+
+    /// Blocks all vertices within the rectangle spanned by `lower` and `upper`, bounds included.
+    /// Returns the number of vertices blocked.
+    ///
+    /// # Arguments
+    ///
+    /// * `coordinates` - Coordinates of all vertices in order, as read by `load_coordinates`.
+    pub fn block_box(
+        &mut self,
+        coordinates: &[Coordinates],
+        lower: &Coordinates,
+        upper: &Coordinates,
+    ) -> usize {
+        let mut count = 0;
+        for (i, c) in coordinates.iter().enumerate() {
+            if (lower.x..=upper.x).contains(&c.x) && (lower.y..=upper.y).contains(&c.y) {
+                self.vertices.insert(i);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Removes all blocks.
+    #[inline]
+    pub fn unblock_all(&mut self) {
+        self.vertices.clear();
+        self.arcs.clear();
+    }
+}
+
+impl<G: Graph> Graph for FilteredGraph<'_, G> {
+    #[inline]
+    fn num_vertices(&self) -> usize {
+        self.graph.num_vertices()
+    }
+
+    #[inline]
+    fn get_neighbors(&self, u: Vertex) -> impl Iterator<Item = &Neighbor> {
+        let first = self.offsets[usize::from(u)];
+        let blocked = self.vertices.contains(usize::from(u));
+        self.graph
+            .get_neighbors(u)
+            .take(if blocked { 0 } else { usize::MAX })
+            .enumerate()
+            .filter(move |(i, e)| {
+                !self.arcs.contains(first + i) && !self.vertices.contains(usize::from(e.to))
+            })
+            .map(|(_, e)| e)
+    }
+}
+
+impl<'a, G: Graph> DicirectionalList<FilteredGraph<'a, G>> {
+    /// Constructs views of both directions of `graph` with nothing blocked.
+    pub fn filtered(graph: &'a DicirectionalList<G>) -> Self {
+        Self {
+            forward: FilteredGraph::new(&graph.forward),
+            backward: FilteredGraph::new(&graph.backward),
+        }
+    }
+
+    #[inline]
+    pub fn block_vertex(&mut self, v: Vertex) {
+        self.forward.block_vertex(v);
+        self.backward.block_vertex(v);
+    }
+
+    #[inline]
+    pub fn unblock_vertex(&mut self, v: Vertex) {
+        self.forward.unblock_vertex(v);
+        self.backward.unblock_vertex(v);
+    }
+
+    /// Blocks the arc with the id `id` in the forward graph in both directions. Of parallel arcs
+    /// the reverse one has the same weight, so both directions keep the same arcs.
+    pub fn block_arc(&mut self, id: usize) {
+        let (from, e) = self.forward.neighbor(id);
+        let (to, weight) = (e.to, e.weight);
+        if let Some(reverse) = self.backward.arc_id_with_weight(to, from, weight) {
+            self.backward.block_arc(reverse);
+        }
+        self.forward.block_arc(id);
+    }
+
+    /// Blocks all vertices within the rectangle spanned by `lower` and `upper` in both
+    /// directions. Returns the number of vertices blocked.
+    pub fn block_box(
+        &mut self,
+        coordinates: &[Coordinates],
+        lower: &Coordinates,
+        upper: &Coordinates,
+    ) -> usize {
+        self.backward.block_box(coordinates, lower, upper);
+        self.forward.block_box(coordinates, lower, upper)
+    }
+
+    #[inline]
+    pub fn unblock_all(&mut self) {
+        self.forward.unblock_all();
+        self.backward.unblock_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{
+        sp_bi, sp_naiv, sssp, Dijkstra, NeighborList, OwnedLookup, StructuredEdges,
+    };
+    use crate::dimacs::tests::random_edges;
+    use crate::dimacs::Edge;
+    use crate::implicit_heaps::BinaryHeap;
+
+    /// Checks that searching the view gives the same distances as searching `expected`.
+    fn assert_same_sssp(view: &impl Graph, expected: &NeighborList, source: Vertex) {
+        let n = expected.len();
+        let filtered = sssp(OwnedLookup::<BinaryHeap>::from((source, n)), view);
+        let rebuilt = sssp(OwnedLookup::<BinaryHeap>::from((source, n)), expected);
+        for i in 0..n {
+            let v = Vertex::try_from(i).unwrap();
+            assert_eq!(
+                filtered.get_dist(v),
+                rebuilt.get_dist(v),
+                "distance of {}",
+                v
+            );
+        }
+    }
+
+    #[test]
+    fn block_vertices() {
+        let n = 300;
+        let edges = random_edges(n, 3 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.clone().into_iter());
+        let mut view = FilteredGraph::new(&graph);
+        let mut rng = thread_rng();
+        let blocked: Vec<Vertex> = (0..30)
+            .map(|_| Vertex(rng.gen_range(2..=n as u32)))
+            .collect();
+        for &v in blocked.iter() {
+            view.block_vertex(v);
+        }
+        let remaining = edges
+            .into_iter()
+            .filter(|e| !blocked.contains(&e.from) && !blocked.contains(&e.to));
+        let expected: NeighborList = StructuredEdges::new(n, remaining);
+        assert_same_sssp(&view, &expected, Vertex(1));
+        view.unblock_all();
+        assert_same_sssp(&view, &graph, Vertex(1));
+    }
+
+    #[test]
+    fn block_arcs() {
+        let n = 300;
+        let edges = random_edges(n, 3 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.clone().into_iter());
+        let mut view = FilteredGraph::new(&graph);
+        assert_eq!(view.num_arcs(), edges.len());
+        let mut rng = thread_rng();
+        let mut blocked: Vec<(Vertex, Vertex)> = Vec::new();
+        for _ in 0..100 {
+            let id = rng.gen_range(0..view.num_arcs());
+            view.block_arc(id);
+            blocked.push(view.arc(id));
+        }
+        let remaining = edges
+            .into_iter()
+            .filter(|e| !blocked.contains(&(e.from, e.to)));
+        let expected: NeighborList = StructuredEdges::new(n, remaining);
+        assert_same_sssp(&view, &expected, Vertex(1));
+        assert_eq!(view.arc_id(blocked[0].0, blocked[0].1), None);
+    }
+
+    #[test]
+    fn block_box_bidirectional() {
+        let n = 400;
+        let edges = random_edges(n, 3 * n);
+        // vertices on a 20x20 grid
+        let coordinates: Vec<Coordinates> = (0..n as i64)
+            .map(|i| Coordinates {
+                x: i % 20,
+                y: i / 20,
+            })
+            .collect();
+        let graph: DicirectionalList<NeighborList> =
+            DicirectionalList::new(n, edges.clone().into_iter());
+        let mut view = DicirectionalList::filtered(&graph);
+        let lower = Coordinates { x: 5, y: 5 };
+        let upper = Coordinates { x: 9, y: 14 };
+        assert_eq!(view.block_box(&coordinates, &lower, &upper), 50);
+        view.block_arc(0);
+        let inside = |v: Vertex| {
+            let c = &coordinates[usize::from(v)];
+            (5..=9).contains(&c.x) && (5..=14).contains(&c.y)
+        };
+        let remaining: Vec<Edge> = edges
+            .into_iter()
+            .skip(1)
+            .filter(|e| !inside(e.from) && !inside(e.to))
+            .collect();
+        let expected: NeighborList = StructuredEdges::new(n, remaining.into_iter());
+        assert_same_sssp(&view.forward, &expected, Vertex(1));
+
+        let mut rng = thread_rng();
+        for _ in 0..30 {
+            let (s, t) = (
+                Vertex(rng.gen_range(1..=n as u32)),
+                Vertex(rng.gen_range(1..=n as u32)),
+            );
+            if inside(s) || inside(t) || s == t {
+                continue;
+            }
+            let naiv = sp_naiv(OwnedLookup::<BinaryHeap>::from((s, n)), t, &expected);
+            let bi = sp_bi(
+                OwnedLookup::<BinaryHeap>::from((s, n)),
+                OwnedLookup::<BinaryHeap>::from((t, n)),
+                &view,
+            );
+            assert_eq!(naiv.map(|(d, _)| d), bi.map(|(d, _)| d));
+        }
+    }
+
+    #[test]
+    fn block_parallel_arcs() {
+        // parallel arcs 1 -> 2 of different weights, the shorter one is blocked
+        let edges = [(1, 2, 10), (1, 2, 1), (2, 3, 1), (3, 1, 1)]
+            .into_iter()
+            .map(|(from, to, weight)| Edge {
+                from: Vertex(from),
+                to: Vertex(to),
+                weight,
+            });
+        let graph: DicirectionalList<NeighborList> = DicirectionalList::new(3, edges);
+        let mut view = DicirectionalList::filtered(&graph);
+        let id = view
+            .forward
+            .arc_id_with_weight(Vertex(1), Vertex(2), 1)
+            .unwrap();
+        view.block_arc(id);
+        assert_eq!(view.forward.arc_id(Vertex(1), Vertex(2)), Some(0));
+        assert_eq!(
+            view.backward.arc_id_with_weight(Vertex(2), Vertex(1), 1),
+            None
+        );
+        assert!(view
+            .backward
+            .arc_id_with_weight(Vertex(2), Vertex(1), 10)
+            .is_some());
+        let bi = sp_bi(
+            OwnedLookup::<BinaryHeap>::from((Vertex(1), 3)),
+            OwnedLookup::<BinaryHeap>::from((Vertex(2), 3)),
+            &view,
+        );
+        assert_eq!(bi.map(|(dist, _)| dist), Some(10));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::dijkstra::{DecreaseKey, InitDijkstra, Item, NoLookup, OwnedLookup, PriorityQueue};
+use crate::dijkstra::{
+    assert_send, Backend, DecreaseKey, Heapify, InitDijkstra, Item, NoLookup, OwnedLookup,
+    PriorityQueue,
+};
 use crate::dimacs::*;
 use macros::PriorityQueue;
 use nohash_hasher::NoHashHasher;
@@ -8,194 +11,292 @@ use std::hash::BuildHasherDefault;
 // The hashmap resizes itself but we chose a good default that works for our use case.
 const DEFAULT_SIZE: usize = 8192;
 
-macro_rules! implicit_heap_simple {
-    ($k:expr, $T:ident) => {
-        // Define a priority queue struct using the given identifier ($T).
-        // This queue uses a simple d_ary heap implementation.
-        #[derive(PriorityQueue)]
-        pub struct $T {
-            inner: Vec<Item>,
+/// Marks a vertex that is not in the heap in [`IndexedPositions`].
+const NOT_IN_HEAP: u32 = u32::MAX;
+
+/// Represents the position index of a heap, mapping every vertex in the heap to its index.
+pub trait Positions {
+    /// Creates an index that holds `size` vertices without reallocating.
+    fn with_capacity(size: usize) -> Self;
+    /// Number of vertices the index holds without reallocating.
+    fn capacity(&self) -> usize;
+    /// Returns the position of a vertex in the heap.
+    fn get(&self, vertex: &Vertex) -> Option<usize>;
+    /// Sets the position of a vertex in the heap.
+    fn insert(&mut self, vertex: Vertex, index: usize);
+    /// Forgets the position of a vertex that left the heap.
+    fn remove(&mut self, vertex: &Vertex);
+    /// Forgets all positions.
+    fn clear(&mut self);
+}
+
+/// Represents a position index hashing the vertices.
+pub type HashedPositions = HashMap<Vertex, usize, BuildHasherDefault<NoHashHasher<u32>>>;
+
+impl Positions for HashedPositions {
+    #[inline]
+    fn with_capacity(size: usize) -> Self {
+        HashMap::with_capacity_and_hasher(size, BuildHasherDefault::default())
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        HashMap::capacity(self)
+    }
+
+    #[inline]
+    fn get(&self, vertex: &Vertex) -> Option<usize> {
+        HashMap::get(self, vertex).copied()
+    }
+
+    #[inline]
+    fn insert(&mut self, vertex: Vertex, index: usize) {
+        HashMap::insert(self, vertex, index);
+    }
+
+    #[inline]
+    fn remove(&mut self, vertex: &Vertex) {
+        HashMap::remove(self, vertex);
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        HashMap::clear(self);
+    }
+}
+
+/// Represents a position index stored in a vector indexed by the vertices.
+///
+/// The vector grows to the largest vertex pushed, so for a graph it ends up sized to the vertex
+/// count.
+pub struct IndexedPositions {
+    positions: Vec<u32>,
+}
+
+impl Positions for IndexedPositions {
+    #[inline]
+    fn with_capacity(size: usize) -> Self {
+        Self {
+            positions: vec![NOT_IN_HEAP; size],
         }
+    }
 
-        impl From<Vertex> for $T {
-            #[inline]
-            fn from(value: Vertex) -> Self {
-                // The hashmap resizes itself but we chose a good default that works for our use case.
-                let mut inner = Vec::with_capacity(DEFAULT_SIZE);
-                inner.push(Item { key: 0, value });
-                Self { inner }
-            }
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.positions.len()
+    }
+
+    #[inline]
+    fn get(&self, vertex: &Vertex) -> Option<usize> {
+        match self.positions.get(usize::from(*vertex)) {
+            Some(&NOT_IN_HEAP) | None => None,
+            Some(&index) => Some(index as usize),
         }
+    }
 
-        impl InitDijkstra for $T {
-            type Data = NoLookup<Self>;
+    #[inline]
+    fn insert(&mut self, vertex: Vertex, index: usize) {
+        let slot = usize::from(vertex);
+        if slot >= self.positions.len() {
+            self.positions.resize(slot + 1, NOT_IN_HEAP);
         }
+        self.positions[slot] = index as u32;
+    }
 
-        impl $T {
-            // Move the item at the specified index up in the heap
-            #[inline]
-            fn bubble_up(&mut self, dirt: usize) {
-                let mut child = dirt;
-
-                let mut parent;
-                while child > 0 {
-                    let heap = &mut self.inner;
-                    parent = (child - 1) / $k;
-                    let p_item = heap[parent];
-                    let c_item = heap[child];
-                    if p_item.key <= c_item.key {
-                        break;
-                    }
-                    heap.swap(parent, child);
-                    child = parent;
-                }
+    #[inline]
+    fn remove(&mut self, vertex: &Vertex) {
+        self.positions[usize::from(*vertex)] = NOT_IN_HEAP;
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        self.positions.fill(NOT_IN_HEAP);
+    }
+}
+
+/// Represents a d-ary heap of arity `D` without a lookup, decreased keys are pushed again.
+#[derive(PriorityQueue)]
+pub struct DaryHeapSimple<const D: usize> {
+    inner: Vec<Item>,
+}
+
+impl<const D: usize> From<Vertex> for DaryHeapSimple<D> {
+    #[inline]
+    fn from(value: Vertex) -> Self {
+        const { assert!(D >= 2, "a d-ary heap needs an arity of at least 2") };
+        // The hashmap resizes itself but we chose a good default that works for our use case.
+        let mut inner = Vec::with_capacity(DEFAULT_SIZE);
+        inner.push(Item { key: 0, value });
+        Self { inner }
+    }
+}
+
+impl<const D: usize> InitDijkstra for DaryHeapSimple<D> {
+    type Data<B: Backend> = NoLookup<Self, B>;
+}
+
+impl<const D: usize> Heapify for DaryHeapSimple<D> {
+    #[inline]
+    fn bubble_up(&mut self, dirt: usize) {
+        let mut child = dirt;
+
+        let mut parent;
+        while child > 0 {
+            let heap = &mut self.inner;
+            parent = (child - 1) / D;
+            let p_item = heap[parent];
+            let c_item = heap[child];
+            if p_item.key <= c_item.key {
+                break;
             }
+            heap.swap(parent, child);
+            child = parent;
+        }
+    }
 
-            // Move the root item down in the heap
-            #[inline]
-            fn bubble_down(&mut self) {
-                let mut parent = 0;
-                let n = self.inner.len();
-
-                let mut child;
-                while {
-                    let heap = &mut self.inner;
-                    let base = parent * $k + 1;
-                    let end = usize::min(base + $k, n);
-                    child = (base..end).reduce(|left, right| {
-                        let l_item = heap[left];
-                        let r_item = heap[right];
-                        if l_item.key > r_item.key {
-                            right
-                        } else {
-                            left
-                        }
-                    });
-                    child != None
-                } {
-                    let child = child.unwrap();
-                    let heap = &self.inner;
-                    let p_item = heap[parent];
-                    let c_item = heap[child];
-                    if p_item.key <= c_item.key {
-                        break;
-                    }
-                    self.inner.swap(parent, child);
-                    parent = child;
+    #[inline]
+    fn bubble_down(&mut self) {
+        let mut parent = 0;
+        let n = self.inner.len();
+
+        let mut child;
+        while {
+            let heap = &mut self.inner;
+            let base = parent * D + 1;
+            let end = usize::min(base + D, n);
+            child = (base..end).reduce(|left, right| {
+                let l_item = heap[left];
+                let r_item = heap[right];
+                if l_item.key > r_item.key {
+                    right
+                } else {
+                    left
                 }
+            });
+            child.is_some()
+        } {
+            let child = child.unwrap();
+            let heap = &self.inner;
+            let p_item = heap[parent];
+            let c_item = heap[child];
+            if p_item.key <= c_item.key {
+                break;
             }
+            self.inner.swap(parent, child);
+            parent = child;
         }
-    };
+    }
 }
 
-macro_rules! implicit_heap {
-    ($k:expr, $T:ident) => {
-        // Define a priority queue struct using the given identifier ($T).
-        // This queue uses a d_ary heap implementation with lookup.
-        #[derive(PriorityQueue)]
-        pub struct $T {
-            inner: Vec<Item>,
-            lookup: HashMap<Vertex, usize, BuildHasherDefault<NoHashHasher<u32>>>,
-        }
+/// Represents a d-ary heap of arity `D` with a lookup of the position of every vertex.
+#[derive(PriorityQueue)]
+pub struct DaryHeap<const D: usize, P: Positions = HashedPositions> {
+    inner: Vec<Item>,
+    lookup: P,
+}
 
-        impl From<Vertex> for $T {
-            #[inline]
-            fn from(value: Vertex) -> Self {
-                //the hashmap resizes itself but we chose a good default that works for our
-                let size = DEFAULT_SIZE;
-                let mut inner = Vec::with_capacity(size);
-                let mut lookup =
-                    HashMap::with_capacity_and_hasher(size, BuildHasherDefault::default());
-                inner.push(Item { key: 0, value });
-                lookup.insert(value, 0);
-                Self { inner, lookup }
-            }
-        }
+impl<const D: usize, P: Positions> From<Vertex> for DaryHeap<D, P> {
+    #[inline]
+    fn from(value: Vertex) -> Self {
+        const { assert!(D >= 2, "a d-ary heap needs an arity of at least 2") };
+        //the hashmap resizes itself but we chose a good default that works for our
+        let size = DEFAULT_SIZE;
+        let mut inner = Vec::with_capacity(size);
+        let mut lookup = P::with_capacity(size);
+        inner.push(Item { key: 0, value });
+        lookup.insert(value, 0);
+        Self { inner, lookup }
+    }
+}
 
-        impl InitDijkstra for $T {
-            type Data = OwnedLookup<Self>;
-        }
+impl<const D: usize, P: Positions> InitDijkstra for DaryHeap<D, P> {
+    type Data<B: Backend> = OwnedLookup<Self, B>;
+}
 
-        impl DecreaseKey for $T {
-            #[inline]
-            fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
-                let index = self.lookup.get(&of).unwrap();
-                let item = &mut self.inner[*index];
-                item.key = key;
-                self.bubble_up(*index);
+impl<const D: usize, P: Positions> DecreaseKey for DaryHeap<D, P> {
+    #[inline]
+    fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
+        let index = self.lookup.get(&of).unwrap();
+        let item = &mut self.inner[index];
+        item.key = key;
+        self.bubble_up(index);
+    }
+}
+
+impl<const D: usize, P: Positions> Heapify for DaryHeap<D, P> {
+    #[inline]
+    fn bubble_up(&mut self, dirt: usize) {
+        let mut child = dirt;
+
+        let mut parent;
+        while child > 0 {
+            let heap = &self.inner;
+            parent = (child - 1) / D;
+            let p_item = heap[parent];
+            let c_item = heap[child];
+            if p_item.key <= c_item.key {
+                break;
             }
+            self.lookup.insert(p_item.value, child);
+            self.lookup.insert(c_item.value, parent);
+            self.inner.swap(parent, child);
+            child = parent;
         }
+    }
 
-        impl $T {
-            // Move the item at the specified index up in the heap
-            #[inline]
-            fn bubble_up(&mut self, dirt: usize) {
-                let mut child = dirt;
-
-                let mut parent;
-                while child > 0 {
-                    let heap = &self.inner;
-                    parent = (child - 1) / $k;
-                    let p_item = heap[parent];
-                    let c_item = heap[child];
-                    if p_item.key <= c_item.key {
-                        break;
-                    }
-                    *self.lookup.get_mut(&p_item.value).unwrap() = child;
-                    *self.lookup.get_mut(&c_item.value).unwrap() = parent;
-                    self.inner.swap(parent, child);
-                    child = parent;
-                }
-            }
+    #[inline]
+    fn bubble_down(&mut self) {
+        let mut parent = 0;
+        let n = self.inner.len();
 
-            // Move the root item down in the heap
-            #[inline]
-            fn bubble_down(&mut self) {
-                let mut parent = 0;
-                let n = self.inner.len();
-
-                let mut child;
-                while {
-                    let heap = &self.inner;
-                    let base = parent * $k + 1;
-                    let end = usize::min(base + $k, n);
-                    child = (base..end).reduce(|left, right| {
-                        let l_item = &heap[left];
-                        let r_item = &heap[right];
-                        if l_item.key > r_item.key {
-                            right
-                        } else {
-                            left
-                        }
-                    });
-                    child != None
-                } {
-                    let heap = &self.inner;
-                    let child = child.unwrap();
-                    let p_item = &heap[parent];
-                    let c_item = &heap[child];
-                    if p_item.key <= c_item.key {
-                        break;
-                    }
-                    *self.lookup.get_mut(&p_item.value).unwrap() = child;
-                    *self.lookup.get_mut(&c_item.value).unwrap() = parent;
-                    self.inner.swap(parent, child);
-                    parent = child;
+        let mut child;
+        while {
+            let heap = &self.inner;
+            let base = parent * D + 1;
+            let end = usize::min(base + D, n);
+            child = (base..end).reduce(|left, right| {
+                let l_item = &heap[left];
+                let r_item = &heap[right];
+                if l_item.key > r_item.key {
+                    right
+                } else {
+                    left
                 }
+            });
+            child.is_some()
+        } {
+            let heap = &self.inner;
+            let child = child.unwrap();
+            let p_item = &heap[parent];
+            let c_item = &heap[child];
+            if p_item.key <= c_item.key {
+                break;
             }
+            self.lookup.insert(p_item.value, child);
+            self.lookup.insert(c_item.value, parent);
+            self.inner.swap(parent, child);
+            parent = child;
         }
-    };
+    }
 }
 
-implicit_heap_simple!(2, BinaryHeapSimple);
-implicit_heap_simple!(4, PentaryHeapSimple);
-implicit_heap_simple!(8, OctaryHeapSimple);
-implicit_heap_simple!(16, HexadecimaryHeapSimple);
+pub type BinaryHeapSimple = DaryHeapSimple<2>;
+pub type PentaryHeapSimple = DaryHeapSimple<4>;
+pub type OctaryHeapSimple = DaryHeapSimple<8>;
+pub type HexadecimaryHeapSimple = DaryHeapSimple<16>;
+
+pub type BinaryHeap = DaryHeap<2>;
+pub type PentaryHeap = DaryHeap<4>;
+pub type OctaryHeap = DaryHeap<8>;
+pub type HexadecimaryHeap = DaryHeap<16>;
 
-implicit_heap!(2, BinaryHeap);
-implicit_heap!(4, PentaryHeap);
-implicit_heap!(8, OctaryHeap);
-implicit_heap!(16, HexadecimaryHeap);
+pub type IndexedDaryHeap<const D: usize> = DaryHeap<D, IndexedPositions>;
+pub type IndexedBinaryHeap = IndexedDaryHeap<2>;
+pub type IndexedPentaryHeap = IndexedDaryHeap<4>;
+
+const _: () = assert_send::<DaryHeap<2>>();
+const _: () = assert_send::<IndexedDaryHeap<2>>();
+const _: () = assert_send::<DaryHeapSimple<2>>();
 
 #[cfg(test)]
 mod tests {
@@ -207,7 +308,7 @@ mod tests {
 
     macro_rules! push_pop_test {
         // using a ty token type for macthing datatypes passed to maccro
-        ($name:ident,$T:ident) => {
+        ($name:ident,$T:ty) => {
             #[test]
             fn $name() {
                 let n = 10000;
@@ -249,15 +350,19 @@ mod tests {
     push_pop_test!(push_pop_4, PentaryHeap);
     push_pop_test!(push_pop_8, OctaryHeap);
     push_pop_test!(push_pop_16, HexadecimaryHeap);
+    push_pop_test!(push_pop_3, DaryHeap<3>);
+    push_pop_test!(push_pop_32, DaryHeap<32>);
+    push_pop_test!(push_pop_2_indexed, IndexedBinaryHeap);
+    push_pop_test!(push_pop_4_indexed, IndexedPentaryHeap);
 
     macro_rules! push_pop_test_simple {
         // using a ty token type for macthing datatypes passed to maccro
-        ($name:ident,$T:ident) => {
+        ($name:ident,$T:ty) => {
             #[test]
             fn $name() {
                 let n = 10000;
                 let mut highest_min = 0;
-                let mut dijkstra: NoLookup<SortetList> = NoLookup::from((Vertex(1), n));
+                let mut dijkstra: NoLookup<$T> = NoLookup::from((Vertex(1), n));
                 let mut rng = thread_rng();
                 //Push
                 for i in 1..n {
@@ -280,8 +385,9 @@ mod tests {
                 //Pop
                 for _ in 0..n {
                     let (key, popped) = dijkstra.pop_min().unwrap();
-                    let (stored_key, _) = dijkstra.meta.get(&popped).unwrap();
-                    assert_eq!(key, stored_key.unwrap());
+                    let (stored_key, _, settled) = dijkstra.meta.get(&popped).unwrap();
+                    assert!(*settled);
+                    assert_eq!(key, *stored_key);
                     assert!(key >= highest_min);
                     highest_min = u32::max(highest_min, key);
                 }
@@ -294,4 +400,147 @@ mod tests {
     push_pop_test_simple!(push_pop_4_simple, PentaryHeapSimple);
     push_pop_test_simple!(push_pop_8_simple, OctaryHeapSimple);
     push_pop_test_simple!(push_pop_16_simple, HexadecimaryHeapSimple);
+    push_pop_test_simple!(push_pop_3_simple, DaryHeapSimple<3>);
+    push_pop_test_simple!(push_pop_32_simple, DaryHeapSimple<32>);
+
+    // a heap with a renamed field, key and value are read from the item type
+    #[derive(PriorityQueue)]
+    struct Renamed {
+        #[pq(heap)]
+        items: Vec<Item<u32, Vertex>>,
+    }
+
+    impl From<Vertex> for Renamed {
+        fn from(value: Vertex) -> Self {
+            Self {
+                items: vec![Item { key: 0, value }],
+            }
+        }
+    }
+
+    impl Heapify for Renamed {
+        fn bubble_up(&mut self, mut index: usize) {
+            while index > 0 && self.items[(index - 1) / 2].key > self.items[index].key {
+                self.items.swap((index - 1) / 2, index);
+                index = (index - 1) / 2;
+            }
+        }
+
+        fn bubble_down(&mut self) {
+            let mut index = 0;
+            loop {
+                let smallest = (2 * index + 1..usize::min(2 * index + 3, self.items.len()))
+                    .min_by_key(|&child| self.items[child].key);
+                match smallest {
+                    Some(child) if self.items[child].key < self.items[index].key => {
+                        self.items.swap(index, child);
+                        index = child;
+                    }
+                    _ => return,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn derive_renamed_fields() {
+        let mut heap = Renamed::from(Vertex(1));
+        heap.push(5, Vertex(2));
+        heap.push(3, Vertex(3));
+        assert_eq!(heap.pop(), Some((0, Vertex(1))));
+        heap.push(4, Vertex(4));
+        assert_eq!(heap.pop(), Some((3, Vertex(3))));
+        assert_eq!(heap.pop(), Some((4, Vertex(4))));
+        assert_eq!(heap.pop(), Some((5, Vertex(2))));
+        assert_eq!(heap.pop(), None);
+        heap.reset(Vertex(9));
+        assert_eq!(heap.pop(), Some((0, Vertex(9))));
+    }
+
+    struct WideItem {
+        key: u64,
+        value: Vertex,
+    }
+
+    // a heap with keys beyond u32 given by attribute and a renamed position index
+    #[derive(PriorityQueue)]
+    #[pq(key = u64)]
+    struct Wide {
+        #[pq(heap)]
+        entries: Vec<WideItem>,
+        #[pq(lookup)]
+        positions: std::collections::HashMap<Vertex, usize>,
+    }
+
+    impl From<Vertex> for Wide {
+        fn from(value: Vertex) -> Self {
+            Self {
+                entries: vec![WideItem { key: 0, value }],
+                positions: [(value, 0)].into(),
+            }
+        }
+    }
+
+    impl Wide {
+        fn swap(&mut self, a: usize, b: usize) {
+            self.entries.swap(a, b);
+            self.positions.insert(self.entries[a].value, a);
+            self.positions.insert(self.entries[b].value, b);
+        }
+    }
+
+    impl Heapify for Wide {
+        fn bubble_up(&mut self, mut index: usize) {
+            while index > 0 && self.entries[(index - 1) / 2].key > self.entries[index].key {
+                self.swap((index - 1) / 2, index);
+                index = (index - 1) / 2;
+            }
+        }
+
+        fn bubble_down(&mut self) {
+            let mut index = 0;
+            loop {
+                let smallest = (2 * index + 1..usize::min(2 * index + 3, self.entries.len()))
+                    .min_by_key(|&child| self.entries[child].key);
+                match smallest {
+                    Some(child) if self.entries[child].key < self.entries[index].key => {
+                        self.swap(index, child);
+                        index = child;
+                    }
+                    _ => return,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn derive_wide_keys_and_lookup() {
+        let mut heap = Wide::from(Vertex(1));
+        let mut rng = thread_rng();
+        let mut keys: Vec<(u64, Vertex)> = (2..200)
+            .map(|v| {
+                (
+                    rng.gen_range(0..u64::MAX / 2) + u64::from(u32::MAX),
+                    Vertex(v),
+                )
+            })
+            .collect();
+        for &(key, value) in keys.iter() {
+            assert_eq!(heap.push(key, value), value);
+        }
+        keys.sort_by_key(|&(key, value)| (key, value.0));
+        assert_eq!(heap.pop(), Some((0, Vertex(1))));
+        for &(key, value) in keys.iter() {
+            // the index follows every move of the heap
+            for (i, entry) in heap.entries.iter().enumerate() {
+                assert_eq!(heap.positions[&entry.value], i);
+            }
+            assert_eq!(heap.pop(), Some((key, value)));
+            assert!(!heap.positions.contains_key(&value));
+        }
+        assert_eq!(heap.pop(), None);
+        heap.reset(Vertex(9));
+        assert_eq!(heap.positions.len(), 1);
+        assert_eq!(heap.pop(), Some((0, Vertex(9))));
+    }
 }
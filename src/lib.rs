@@ -1,5 +1,20 @@
 pub mod all_pairs;
+pub mod bucket_queue;
+pub mod checkpoint;
+pub mod components;
+pub mod compressed;
+pub mod context;
+pub mod delta_stepping;
 pub mod dijkstra;
 pub mod dimacs;
+pub mod dynamic;
+pub mod fibonacci_heap;
+pub mod filter;
 pub mod implicit_heaps;
+pub mod min_plus;
 pub mod pairing_heap;
+pub mod radix_heap;
+pub mod rank_pairing_heap;
+pub mod rcsp;
+pub mod renumber;
+pub mod turns;
@@ -0,0 +1,312 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// Number of rows of the result updated together.
+const ROWS: usize = 4;
+/// Number of inner indices processed before moving on to the next columns.
+const INNER_TILE: usize = 256;
+
+/// Represents an implementation of the min-plus kernel.
+///
+/// The vector kernels keep a block of rows of the result in registers while they iterate over a
+/// tile of the inner dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "x86_64")]
+    Avx512,
+}
+
+impl Kernel {
+    /// Returns the fastest kernel supported by the running CPU.
+    #[inline]
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx512f") {
+                return Kernel::Avx512;
+            }
+            if is_x86_feature_detected!("avx2") {
+                return Kernel::Avx2;
+            }
+        }
+        Kernel::Scalar
+    }
+
+    /// Relaxes `c` by the min-plus product of `a` and `b`, see [`min_plus`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the kernel is not supported by the running CPU or the slices do not match the
+    /// dimensions.
+    pub fn min_plus(
+        self,
+        c: &mut [u32],
+        a: &[u32],
+        b: &[u32],
+        rows: usize,
+        inner: usize,
+        cols: usize,
+    ) {
+        assert_eq!(c.len(), rows * cols, "c has to be rows x cols");
+        assert_eq!(a.len(), rows * inner, "a has to be rows x inner");
+        assert_eq!(b.len(), inner * cols, "b has to be inner x cols");
+        match self {
+            Kernel::Scalar => scalar(c, a, b, rows, inner, cols),
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => {
+                assert!(is_x86_feature_detected!("avx2"), "avx2 is not supported");
+                // SAFETY: the feature was detected and the dimensions were checked
+                unsafe { avx2(c, a, b, rows, inner, cols) }
+            }
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx512 => {
+                assert!(
+                    is_x86_feature_detected!("avx512f"),
+                    "avx512f is not supported"
+                );
+                // SAFETY: the feature was detected and the dimensions were checked
+                unsafe { avx512(c, a, b, rows, inner, cols) }
+            }
+        }
+    }
+}
+
+/// Relaxes the `rows` x `cols` matrix `c` by the min-plus product of the `rows` x `inner`
+/// matrix `a` and the `inner` x `cols` matrix `b`, so that `c[i][j]` becomes the minimum of
+/// itself and `a[i][k] + b[k][j]` over all `k`. Matrices are stored row-major, `u32::MAX`
+/// is infinity and sums saturate at it. The kernel is chosen by [`Kernel::detect`].
+#[inline]
+pub fn min_plus(c: &mut [u32], a: &[u32], b: &[u32], rows: usize, inner: usize, cols: usize) {
+    Kernel::detect().min_plus(c, a, b, rows, inner, cols)
+}
+
+/// Calculates the min-plus product of the `rows` x `inner` matrix `a` and the `inner` x `cols`
+/// matrix `b`.
+pub fn min_plus_product(a: &[u32], b: &[u32], rows: usize, inner: usize, cols: usize) -> Vec<u32> {
+    let mut c = vec![u32::MAX; rows * cols];
+    min_plus(&mut c, a, b, rows, inner, cols);
+    c
+}
+
+/// Closes the `n` x `n` distance matrix `d` in place with the Warshall-Floyd algorithm, using
+/// every vertex of the matrix as intermediate.
+pub fn closure(d: &mut [u32], n: usize) {
+    assert_eq!(d.len(), n * n, "d has to be n x n");
+    let kernel = Kernel::detect();
+    let (mut column, mut row) = (vec![0; n], vec![0; n]);
+    for k in 0..n {
+        // row and column k do not change in round k as the weights are not negative
+        for (i, cell) in column.iter_mut().enumerate() {
+            *cell = d[i * n + k];
+        }
+        row.copy_from_slice(&d[k * n..(k + 1) * n]);
+        kernel.min_plus(d, &column, &row, n, 1, n);
+    }
+}
+
+/// Scalar min-plus kernel for the rows `rows`, inner indices `inner` and columns `cols` of the
+/// full matrices with `width` inner and `cols.end` or more columns.
+#[inline(always)]
+fn scalar_tile(
+    c: &mut [u32],
+    a: &[u32],
+    b: &[u32],
+    (width, stride): (usize, usize),
+    rows: std::ops::Range<usize>,
+    inner: std::ops::Range<usize>,
+    cols: std::ops::Range<usize>,
+) {
+    for i in rows {
+        for k in inner.clone() {
+            let aik = a[i * width + k];
+            if aik == u32::MAX {
+                continue;
+            }
+            for j in cols.clone() {
+                let via = aik.saturating_add(b[k * stride + j]);
+                if via < c[i * stride + j] {
+                    c[i * stride + j] = via;
+                }
+            }
+        }
+    }
+}
+
+fn scalar(c: &mut [u32], a: &[u32], b: &[u32], rows: usize, inner: usize, cols: usize) {
+    scalar_tile(c, a, b, (inner, cols), 0..rows, 0..inner, 0..cols);
+}
+
+/// Generates a min-plus kernel for one vector extension. `a + b` saturates as
+/// `min(b, MAX - a) + a`, which cannot overflow.
+#[cfg(target_arch = "x86_64")]
+macro_rules! simd_kernel {
+    ($name:ident, $feature:literal, $vec:ty, $lanes:literal, $load:ident, $store:ident,
+     $set1:ident, $min:ident, $add:ident) => {
+        #[target_feature(enable = $feature)]
+        unsafe fn $name(
+            c: &mut [u32],
+            a: &[u32],
+            b: &[u32],
+            rows: usize,
+            inner: usize,
+            cols: usize,
+        ) {
+            let vectorized = cols - cols % $lanes;
+            let (c, a, b) = (c.as_mut_ptr(), a.as_ptr(), b.as_ptr());
+            let mut i = 0;
+            while i < rows {
+                let block = usize::min(ROWS, rows - i);
+                for k0 in (0..inner).step_by(INNER_TILE) {
+                    let k1 = usize::min(k0 + INNER_TILE, inner);
+                    for j in (0..vectorized).step_by($lanes) {
+                        let mut acc: [$vec; ROWS] = [$set1(-1); ROWS];
+                        for r in 0..block {
+                            acc[r] = $load(c.add((i + r) * cols + j).cast());
+                        }
+                        for k in k0..k1 {
+                            let bkj = $load(b.add(k * cols + j).cast());
+                            for r in 0..block {
+                                let aik = *a.add((i + r) * inner + k);
+                                // blocks of sparse graphs are mostly infinite
+                                if aik == u32::MAX {
+                                    continue;
+                                }
+                                let via = $add(
+                                    $min(bkj, $set1((u32::MAX - aik) as i32)),
+                                    $set1(aik as i32),
+                                );
+                                acc[r] = $min(acc[r], via);
+                            }
+                        }
+                        for r in 0..block {
+                            $store(c.add((i + r) * cols + j).cast(), acc[r]);
+                        }
+                    }
+                }
+                i += block;
+            }
+            if vectorized < cols {
+                let (c, a, b) = (
+                    std::slice::from_raw_parts_mut(c, rows * cols),
+                    std::slice::from_raw_parts(a, rows * inner),
+                    std::slice::from_raw_parts(b, inner * cols),
+                );
+                scalar_tile(c, a, b, (inner, cols), 0..rows, 0..inner, vectorized..cols);
+            }
+        }
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+simd_kernel!(
+    avx2,
+    "avx2",
+    __m256i,
+    8,
+    _mm256_loadu_si256,
+    _mm256_storeu_si256,
+    _mm256_set1_epi32,
+    _mm256_min_epu32,
+    _mm256_add_epi32
+);
+
+#[cfg(target_arch = "x86_64")]
+simd_kernel!(
+    avx512,
+    "avx512f",
+    __m512i,
+    16,
+    _mm512_loadu_si512,
+    _mm512_storeu_si512,
+    _mm512_set1_epi32,
+    _mm512_min_epu32,
+    _mm512_add_epi32
+);
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    /// Generates a matrix with some infinite and some large entries that saturate when added.
+    fn random_matrix(rows: usize, cols: usize) -> Vec<u32> {
+        let mut rng = thread_rng();
+        (0..rows * cols)
+            .map(|_| match rng.gen_range(0..10) {
+                0 => u32::MAX,
+                1 => u32::MAX - rng.gen_range(0..1000),
+                _ => rng.gen_range(0..1000),
+            })
+            .collect()
+    }
+
+    fn kernels() -> Vec<Kernel> {
+        let mut kernels = vec![Kernel::Scalar];
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                kernels.push(Kernel::Avx2);
+            }
+            if is_x86_feature_detected!("avx512f") {
+                kernels.push(Kernel::Avx512);
+            }
+        }
+        kernels
+    }
+
+    #[test]
+    fn kernels_match_scalar() {
+        // shapes with remainders in every dimension and more than one inner tile
+        for (rows, inner, cols) in [
+            (1, 1, 1),
+            (4, 8, 16),
+            (7, 300, 37),
+            (13, 5, 64),
+            (33, 513, 9),
+        ] {
+            let (a, b) = (random_matrix(rows, inner), random_matrix(inner, cols));
+            let c = random_matrix(rows, cols);
+            let mut expected = c.clone();
+            scalar(&mut expected, &a, &b, rows, inner, cols);
+            for kernel in kernels() {
+                let mut result = c.clone();
+                kernel.min_plus(&mut result, &a, &b, rows, inner, cols);
+                assert_eq!(result, expected, "{kernel:?} {rows}x{inner}x{cols}");
+            }
+        }
+    }
+
+    #[test]
+    fn product_and_closure() {
+        let inf = u32::MAX;
+        // a path 0 -> 1 -> 2 with a detour 0 -> 2
+        let mut d = vec![0, 2, 9, inf, 0, 3, inf, inf, 0];
+        assert_eq!(
+            min_plus_product(&d, &d, 3, 3, 3),
+            vec![0, 2, 5, inf, 0, 3, inf, inf, 0]
+        );
+        closure(&mut d, 3);
+        assert_eq!(d, vec![0, 2, 5, inf, 0, 3, inf, inf, 0]);
+
+        let n = 70;
+        let mut d = random_matrix(n, n);
+        for i in 0..n {
+            d[i * n + i] = 0;
+        }
+        let mut expected = d.clone();
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    let via = expected[i * n + k].saturating_add(expected[k * n + j]);
+                    expected[i * n + j] = u32::min(expected[i * n + j], via);
+                }
+            }
+        }
+        closure(&mut d, n);
+        assert_eq!(d, expected);
+    }
+}
@@ -1,83 +1,294 @@
-use std::{
-    cell::RefCell,
-    rc::{Rc, Weak},
-};
+use std::marker::PhantomData;
 
 use crate::{dijkstra::*, dimacs::Vertex};
 
-type Link = Option<Rc<RefCell<Node>>>;
-type WeakLink = Option<Weak<RefCell<Node>>>;
+/// Marks a missing link.
+const NIL: u32 = u32::MAX;
+
+/// Represents the strategy used to combine a list of trees into one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pairing {
+    /// Pairs neighbors repeatedly until a single tree is left.
+    Multipass,
+    /// Pairs neighbors from front to back, then merges the pairs from back to front.
+    #[default]
+    TwoPass,
+    /// Pairs neighbors from front to back, then merges the pairs from front to back.
+    TwoPassReverse,
+}
+
+/// Represents a strategy to combine a list of siblings into a single tree.
+pub trait Merge {
+    /// The pairing applied to the list.
+    const PAIRING: Pairing;
+}
+
+/// Merges with [`Pairing::Multipass`].
+#[derive(Debug)]
+pub struct Multipass;
+
+/// Merges with [`Pairing::TwoPass`].
+#[derive(Debug)]
+pub struct TwoPass;
+
+/// Merges with [`Pairing::TwoPassReverse`].
+#[derive(Debug)]
+pub struct TwoPassReverse;
+
+impl Merge for Multipass {
+    const PAIRING: Pairing = Pairing::Multipass;
+}
+
+impl Merge for TwoPass {
+    const PAIRING: Pairing = Pairing::TwoPass;
+}
+
+impl Merge for TwoPassReverse {
+    const PAIRING: Pairing = Pairing::TwoPassReverse;
+}
+
+/// Represents where new and decreased nodes go.
+pub trait Insertion {
+    /// Whether nodes are collected in the auxiliary list instead of being merged with the root.
+    const AUXILIARY: bool;
+}
+
+/// Collects new and decreased nodes in an auxiliary list that is merged on the next pop.
+#[derive(Debug)]
+pub struct Auxiliary;
+
+/// Merges new and decreased nodes with the root right away.
+#[derive(Debug)]
+pub struct Eager;
+
+impl Insertion for Auxiliary {
+    const AUXILIARY: bool = true;
+}
+
+impl Insertion for Eager {
+    const AUXILIARY: bool = false;
+}
 
 /// Represents a node in the pairing heap.
 #[derive(Debug)]
-pub struct Node {
-    /// The identifier of the node.
-    id: Vertex,
-    /// The key value of the node (current distance).
+struct Node {
     key: u32,
-    /// A weak reference to the parent node.
-    parent: WeakLink,
-    /// A strong reference to the first child node.
-    child: Link,
-    /// A strong reference to the next sibling node.
-    next: Link,
+    value: Vertex,
+    /// The first child.
+    child: u32,
+    /// The next sibling.
+    next: u32,
+    /// The previous sibling, or the parent of a first child.
+    prev: u32,
+}
+
+/// Represents the trees of a pairing heap, whose nodes are stored in an arena and referenced by
+/// index.
+///
+/// Every node knows its previous sibling or parent, so a node is cut from its tree in constant
+/// time. Nodes are never reused until the forest is cleared, so handles of popped items stay
+/// unique.
+#[derive(Debug, Default)]
+struct Forest {
+    nodes: Vec<Node>,
+    /// Buffer for the trees to combine.
+    scratch: Vec<u32>,
 }
 
-impl PartialEq for Node {
+impl Forest {
     #[inline]
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+    fn node(&self, id: u32) -> &Node {
+        &self.nodes[id as usize]
     }
-}
 
-impl From<Vertex> for Link {
     #[inline]
-    fn from(value: Vertex) -> Self {
-        if value == Vertex(0) {
-            None
+    fn node_mut(&mut self, id: u32) -> &mut Node {
+        &mut self.nodes[id as usize]
+    }
+
+    /// Adds a single node tree.
+    #[inline]
+    fn push(&mut self, key: u32, value: Vertex) -> u32 {
+        let id = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            key,
+            value,
+            child: NIL,
+            next: NIL,
+            prev: NIL,
+        });
+        id
+    }
+
+    /// Links two roots, returning the new root.
+    #[inline]
+    fn link(&mut self, a: u32, b: u32) -> u32 {
+        match (a, b) {
+            (NIL, root) | (root, NIL) => return root,
+            _ => {}
+        }
+        let (winner, loser) = if self.node(b).key < self.node(a).key {
+            (b, a)
         } else {
-            Some(Rc::new(RefCell::new(Node {
-                id: value,
-                key: 0,
-                parent: None,
-                child: None,
-                next: None,
-            })))
+            (a, b)
+        };
+        let child = self.node(winner).child;
+        if child != NIL {
+            self.node_mut(child).prev = loser;
         }
+        let node = self.node_mut(loser);
+        node.next = child;
+        node.prev = winner;
+        self.node_mut(winner).child = loser;
+        winner
     }
-}
 
-impl From<Vertex> for WeakLink {
+    /// Cuts a node with its subtree from its tree, returns whether it was not a root.
     #[inline]
-    fn from(value: Vertex) -> Self {
-        if value == Vertex(0) {
-            None
+    fn cut(&mut self, id: u32) -> bool {
+        let (prev, next) = (self.node(id).prev, self.node(id).next);
+        if prev == NIL {
+            return false;
+        }
+        if self.node(prev).child == id {
+            self.node_mut(prev).child = next;
         } else {
-            let link = Link::from(value);
-            Some(Rc::downgrade(&link.unwrap()))
+            self.node_mut(prev).next = next;
+        }
+        if next != NIL {
+            self.node_mut(next).prev = prev;
+        }
+        let node = self.node_mut(id);
+        node.prev = NIL;
+        node.next = NIL;
+        true
+    }
+
+    /// Links neighboring roots in the scratch buffer, halving its length.
+    #[inline]
+    fn pair_neighbors(&mut self) {
+        let len = self.scratch.len();
+        for i in 0..len / 2 {
+            let merged = self.link(self.scratch[2 * i], self.scratch[2 * i + 1]);
+            self.scratch[i] = merged;
+        }
+        if len % 2 == 1 {
+            self.scratch[len / 2] = self.scratch[len - 1];
+        }
+        self.scratch.truncate(len.div_ceil(2));
+    }
+
+    /// Combines the roots in the scratch buffer into one tree.
+    fn combine(&mut self, pairing: Pairing) -> u32 {
+        if self.scratch.is_empty() {
+            return NIL;
+        }
+        self.pair_neighbors();
+        match pairing {
+            Pairing::Multipass => {
+                while self.scratch.len() > 1 {
+                    self.pair_neighbors();
+                }
+                self.scratch[0]
+            }
+            Pairing::TwoPass => {
+                let mut root = self.scratch.pop().unwrap_or(NIL);
+                while let Some(tree) = self.scratch.pop() {
+                    root = self.link(tree, root);
+                }
+                root
+            }
+            Pairing::TwoPassReverse => {
+                let mut root = self.scratch[0];
+                for i in 1..self.scratch.len() {
+                    root = self.link(root, self.scratch[i]);
+                }
+                root
+            }
         }
     }
+
+    /// Removes the minimum of `main` and the trees in `aux`, leaving the remaining nodes in
+    /// `main`.
+    #[inline]
+    fn pop(
+        &mut self,
+        main: &mut u32,
+        aux: &mut Vec<u32>,
+        aux_pairing: Pairing,
+        child_pairing: Pairing,
+    ) -> Option<(u32, Vertex)> {
+        self.scratch.clear();
+        self.scratch.append(aux);
+        let aux = self.combine(aux_pairing);
+        let top = self.link(*main, aux);
+        if top == NIL {
+            return None;
+        }
+        //abandon children
+        self.scratch.clear();
+        let mut child = self.node(top).child;
+        while child != NIL {
+            let next = self.node(child).next;
+            let node = self.node_mut(child);
+            node.prev = NIL;
+            node.next = NIL;
+            self.scratch.push(child);
+            child = next;
+        }
+        self.node_mut(top).child = NIL;
+        //join the family
+        *main = self.combine(child_pairing);
+        let node = self.node(top);
+        Some((node.key, node.value))
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        self.nodes.clear();
+    }
 }
 
 /// Represents a pairing heap.
+///
+/// `A` combines the auxiliary list, `C` the children of a popped root and `I` decides whether
+/// the auxiliary list is used at all.
 #[derive(Debug)]
-pub struct PairingHeap {
-    main: Link,
-    aux: Link,
+pub struct PairingHeap<A: Merge = Multipass, C: Merge = TwoPass, I: Insertion = Auxiliary> {
+    forest: Forest,
+    main: u32,
+    aux: Vec<u32>,
+    strategy: PhantomData<(A, C, I)>,
 }
 
-impl From<Vertex> for PairingHeap {
+impl<A: Merge, C: Merge, I: Insertion> From<Vertex> for PairingHeap<A, C, I> {
     #[inline]
     fn from(value: Vertex) -> Self {
-        Self {
-            main: Link::from(value),
-            aux: None,
+        let mut heap = Self {
+            forest: Forest::default(),
+            main: NIL,
+            aux: Vec::new(),
+            strategy: PhantomData,
+        };
+        heap.push(0, value);
+        heap
+    }
+}
+
+impl<A: Merge, C: Merge, I: Insertion> PairingHeap<A, C, I> {
+    /// Adds a single tree to the heap.
+    #[inline]
+    fn insert(&mut self, id: u32) {
+        if I::AUXILIARY {
+            self.aux.push(id);
+        } else {
+            self.main = self.forest.link(self.main, id);
         }
     }
 }
 
-impl PriorityQueue for PairingHeap {
-    type RefType = WeakLink;
+impl<A: Merge, C: Merge, I: Insertion> PriorityQueue for PairingHeap<A, C, I> {
+    type RefType = Handle;
 
     type Key = u32;
 
@@ -85,264 +296,151 @@ impl PriorityQueue for PairingHeap {
 
     #[inline]
     fn is_empty(&self) -> bool {
-        if let None = self.main {
-            true
-        } else {
-            false
-        }
+        self.main == NIL && self.aux.is_empty()
     }
 
     #[inline]
     fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
-        let aux_joined = multipass(self.aux.clone());
-        self.aux = None;
-
-        let combine = match (self.main.clone(), aux_joined.clone()) {
-            (Some(main), aux) => {
-                main.borrow_mut().next = aux;
-                merge_pair(Some(main)).0
-            }
-            (None, aux) => aux,
-        };
-
-        if let Some(top) = combine {
-            let scattered = top.borrow().child.clone();
-            let key = top.borrow().key;
-            let id = top.borrow().id;
-            top.borrow_mut().child = None;
-
-            //abandon children
-            let mut curr = scattered.clone();
-            while let Some(c) = curr {
-                c.borrow_mut().parent = None;
-                curr = c.borrow().next.clone();
-            }
-            //join the family
-            self.main = two_pass(scattered);
-
-            return Some((key, id));
-        }
-        None
+        self.forest
+            .pop(&mut self.main, &mut self.aux, A::PAIRING, C::PAIRING)
     }
 
     #[inline]
     fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
-        let new = Some(Rc::new(RefCell::new(Node {
-            id: value,
-            key,
-            parent: None,
-            child: None,
-            next: self.aux.clone(),
-        })));
-        self.aux = new.clone();
-        Some(Rc::downgrade(&new.unwrap()))
+        let id = self.forest.push(key, value);
+        self.insert(id);
+        Handle(id)
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        self.forest.clear();
+        self.main = NIL;
+        self.aux.clear();
+        self.push(0, source);
     }
 }
 
-impl InitDijkstra for PairingHeap {
-    type Data = Search<Self>;
+impl<A: Merge, C: Merge, I: Insertion> InitDijkstra for PairingHeap<A, C, I> {
+    type Data<B: Backend> = Search<Self, B>;
 }
 
-impl DecreaseKey for PairingHeap {
+impl<A: Merge, C: Merge, I: Insertion> DecreaseKey for PairingHeap<A, C, I> {
+    #[inline]
     fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
-        //panics if link is empty
-        let target = of.unwrap().upgrade().unwrap();
-        let parent = target.borrow().parent.clone();
-        if target.borrow().id == Vertex(2868) {}
-        if let Some(parent) = parent {
-            //SAFETY: all values are usually kept alive by the hashmap
-            let parent = parent.upgrade().unwrap();
-            let siblings = target.borrow().next.clone();
-            target.borrow_mut().parent = None;
-            //we all ready know this parent has children
-            let first_child = parent.borrow().child.clone().unwrap();
-            if first_child == target {
-                parent.borrow_mut().child = siblings;
-            } else {
-                let mut curr_child = first_child;
-                loop {
-                    let next = curr_child.borrow().next.clone().unwrap();
-                    if next == target {
-                        curr_child.borrow_mut().next = siblings;
-                        break;
-                    }
-                    curr_child = next.clone();
-                }
-            }
-            target.borrow_mut().next = self.aux.clone();
-            self.aux = Some(target.clone());
+        let id = of.0;
+        self.forest.node_mut(id).key = key;
+        // roots keep their place
+        if self.forest.cut(id) {
+            self.insert(id);
         }
-        target.borrow_mut().key = key;
     }
 }
 
-/// Utility function to find a node in a linked structure.
-#[allow(dead_code)]
-fn find_in_link(link: Link, id: Vertex) -> bool {
-    match link {
-        None => false,
-        Some(node) => {
-            if node.clone().borrow().id == id {
-                true
-            } else {
-                find_in_link(node.borrow().child.clone(), id)
-                    || find_in_link(node.borrow().next.clone(), id)
-            }
-        }
-    }
+/// Represents a pairing heap whose strategy for combining trees is chosen at runtime.
+///
+/// Like [`PairingHeap`] new and decreased items are collected in an auxiliary list which is
+/// combined on the next pop, here with the same strategy as the children of a popped root.
+#[derive(Debug)]
+pub struct ArenaPairingHeap {
+    forest: Forest,
+    main: u32,
+    aux: Vec<u32>,
+    pairing: Pairing,
 }
 
-/// Utility function to merge a pair of nodes.
-#[inline]
-fn merge_pair(first: Link) -> (Link, Link) {
-    let (a, b) = if let Some(a) = first {
-        if let Some(b) = &a.borrow().next {
-            (a.clone(), b.clone())
-        } else {
-            return (Some(a.clone()), None);
-        }
-    } else {
-        return (None, None);
-    };
-
-    let remainder = b.borrow().next.clone();
-    if a.borrow().key < b.borrow().key {
-        let child = a.borrow().child.clone();
-        b.borrow_mut().next = child;
-        b.borrow_mut().parent = Some(Rc::downgrade(&a));
-        // assert_eq!(a.borrow().parent, None);
-        a.borrow_mut().child = Some(b.clone());
-        a.borrow_mut().next = remainder.clone();
-        return (Some(a), remainder);
-    } else {
-        let child = b.borrow().child.clone();
-        a.borrow_mut().next = child;
-        a.borrow_mut().parent = Some(Rc::downgrade(&b));
-        // assert_eq!(b.borrow().parent, None);
-        b.borrow_mut().child = Some(a.clone());
-        return (Some(b), remainder);
+impl From<Vertex> for ArenaPairingHeap {
+    #[inline]
+    fn from(value: Vertex) -> Self {
+        Self::with_pairing(value, Pairing::default())
     }
 }
 
-/// Utility function to merge nodes from front to back.
-#[inline]
-fn merge_front_to_back(start: Link) -> Link {
-    let mut current = start.clone();
-    loop {
-        let (merged, remainder) = merge_pair(current);
-        current = merged.clone();
-        if let None = remainder {
-            return current;
-        }
-        continue;
+impl ArenaPairingHeap {
+    /// Creates a heap containing the source which combines trees with the given strategy.
+    pub fn with_pairing(source: Vertex, pairing: Pairing) -> Self {
+        let mut heap = Self {
+            forest: Forest::default(),
+            main: NIL,
+            aux: Vec::new(),
+            pairing,
+        };
+        heap.push(0, source);
+        heap
     }
-}
 
-/// Utility function to merge nodes from back to front.
-fn merge_back_to_front(current: Link) -> Link {
-    match current {
-        Some(node) => {
-            let next = node.borrow().next.clone();
-            node.borrow_mut().next = merge_back_to_front(next);
-            merge_pair(Some(node)).0
-        }
-        None => None,
+    /// Returns the strategy used to combine trees.
+    #[inline]
+    pub fn pairing(&self) -> Pairing {
+        self.pairing
     }
 }
 
-/// Utility function for multipass merging of nodes.
-fn multipass(start: Link) -> Link {
-    let mut current = start;
-    let mut next_round: Link = None;
-    loop {
-        match merge_pair(current) {
-            (Some(merged), None) => {
-                if let None = next_round {
-                    return Some(merged);
-                }
-                merged.borrow_mut().next = next_round.clone();
-                return multipass(Some(merged));
-            }
-            (Some(merged), remainder) => {
-                merged.borrow_mut().next = next_round.clone();
-                next_round = Some(merged);
-                current = remainder;
-                continue;
-            }
-            (None, _) => {
-                return None;
-            }
-        }
+impl PriorityQueue for ArenaPairingHeap {
+    type RefType = Handle;
+    type Key = u32;
+    type Value = Vertex;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.main == NIL && self.aux.is_empty()
     }
-}
 
-/// Utility function for two-pass merging of nodes in reverse.
-#[allow(dead_code)]
-#[inline]
-fn two_pass(start: Link) -> Link {
-    let mut current = start;
-    let mut second_round: Link = None;
-    loop {
-        match merge_pair(current) {
-            (Some(merged), None) => {
-                if let None = second_round {
-                    return Some(merged);
-                }
-                merged.borrow_mut().next = second_round.clone();
-                return merge_front_to_back(Some(merged));
-            }
-            (Some(merged), remainder) => {
-                merged.borrow_mut().next = second_round.clone();
-                second_round = Some(merged);
-                current = remainder;
-                continue;
-            }
-            (None, _) => {
-                return None;
-            }
-        }
+    #[inline]
+    fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
+        self.forest
+            .pop(&mut self.main, &mut self.aux, self.pairing, self.pairing)
+    }
+
+    #[inline]
+    fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
+        let id = self.forest.push(key, value);
+        self.aux.push(id);
+        Handle(id)
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        self.forest.clear();
+        self.main = NIL;
+        self.aux.clear();
+        self.push(0, source);
     }
 }
 
-/// Utility function for two-pass merging of nodes in reverse.
-#[allow(dead_code)]
-#[inline]
-fn two_pass_reverse(start: Link) -> Link {
-    let mut current = start;
-    let mut second_round: Link = None;
-    loop {
-        match merge_pair(current) {
-            (Some(merged), None) => {
-                if let None = second_round {
-                    return Some(merged);
-                }
-                merged.borrow_mut().next = second_round.clone();
-                return merge_back_to_front(Some(merged));
-            }
-            (Some(merged), remainder) => {
-                merged.borrow_mut().next = second_round.clone();
-                second_round = Some(merged);
-                current = remainder;
-                continue;
-            }
-            (None, _) => {
-                return None;
-            }
+impl DecreaseKey for ArenaPairingHeap {
+    #[inline]
+    fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
+        let id = of.0;
+        self.forest.node_mut(id).key = key;
+        // roots keep their place
+        if self.forest.cut(id) {
+            self.aux.push(id);
         }
     }
 }
 
+impl InitDijkstra for ArenaPairingHeap {
+    type Data<B: Backend> = Search<Self, B>;
+}
+
+const _: () = assert_send::<PairingHeap>();
+const _: () = assert_send::<PairingHeap<TwoPass, TwoPassReverse, Eager>>();
+const _: () = assert_send::<ArenaPairingHeap>();
+
 #[cfg(test)]
 mod tests {
 
     use crate::dijkstra::Search;
+    use crate::dimacs::tests::random_edges;
+    use crate::implicit_heaps::BinaryHeap;
 
     use super::*;
     use rand::{thread_rng, Rng};
 
     #[test]
     fn simple_merge() {
-        let mut heap = PairingHeap::from(Vertex(1));
+        let mut heap: PairingHeap = PairingHeap::from(Vertex(1));
         heap.push(2, Vertex(2));
         let this = heap.push(4, Vertex(3));
         assert_eq!(heap.pop(), Some((0, Vertex(1))));
@@ -353,7 +451,7 @@ mod tests {
 
     #[test]
     fn test_leakage() {
-        let mut heap = PairingHeap::from(Vertex(1));
+        let mut heap: PairingHeap = PairingHeap::from(Vertex(1));
         assert_eq!(heap.pop(), Some((0, Vertex(1))));
         let mut pushed = Vec::new();
         for i in 100..200 {
@@ -361,7 +459,7 @@ mod tests {
             pushed.push((heap.push(i * 2, Vertex(i)), i));
         }
         for (link, i) in pushed.iter() {
-            heap.decrease_key(link.clone(), *i);
+            heap.decrease_key(*link, *i);
             let popped = heap.pop();
             assert_eq!(popped, Some((*i, Vertex(*i))));
         }
@@ -403,14 +501,109 @@ mod tests {
             let (_, stored_key, _) = dijkstra
                 .meta
                 .remove(&popped)
-                .expect(&format!("popped {:?}", &popped));
+                .unwrap_or_else(|| panic!("popped {:?}", &popped));
             assert_eq!(key, stored_key);
             assert!(key >= highest_min);
             highest_min = u32::max(highest_min, key);
         }
         assert_eq!(None, dijkstra.pop_min());
-        for (link, _, _) in dijkstra.meta.values() {
-            assert_eq!(None, link.clone().unwrap().upgrade())
+        assert!(dijkstra.meta.is_empty());
+    }
+
+    fn sssp_with<A: Merge, C: Merge, I: Insertion>() {
+        let n = 2000;
+        let edges = random_edges(n, 4 * n);
+        let graph: NeighborList = StructuredEdges::new(n, edges.into_iter());
+        let expected = sssp(OwnedLookup::<BinaryHeap>::from((Vertex(1), n)), &graph);
+        let result = sssp(Search::<PairingHeap<A, C, I>>::from((Vertex(1), n)), &graph);
+        for i in 0..n {
+            let v = Vertex::try_from(i).unwrap();
+            assert_eq!(result.get_dist(v), expected.get_dist(v));
+        }
+    }
+
+    #[test]
+    fn merge_strategies() {
+        sssp_with::<Multipass, TwoPass, Auxiliary>();
+        sssp_with::<Multipass, Multipass, Auxiliary>();
+        sssp_with::<TwoPass, TwoPassReverse, Auxiliary>();
+        sssp_with::<TwoPassReverse, TwoPass, Auxiliary>();
+        sssp_with::<Multipass, TwoPass, Eager>();
+        sssp_with::<Multipass, TwoPassReverse, Eager>();
+    }
+
+    #[test]
+    fn simple_merge_arena() {
+        for pairing in [
+            Pairing::Multipass,
+            Pairing::TwoPass,
+            Pairing::TwoPassReverse,
+        ] {
+            let mut heap = ArenaPairingHeap::with_pairing(Vertex(1), pairing);
+            heap.push(2, Vertex(2));
+            let this = heap.push(4, Vertex(3));
+            assert_eq!(heap.pop(), Some((0, Vertex(1))));
+            heap.decrease_key(this, 1);
+            assert_eq!(heap.pop(), Some((1, Vertex(3))));
+            assert_eq!(heap.pop(), Some((2, Vertex(2))));
+            assert_eq!(heap.pop(), None);
+            heap.reset(Vertex(9));
+            assert_eq!(heap.pairing(), pairing);
+            assert_eq!(heap.pop(), Some((0, Vertex(9))));
+        }
+    }
+
+    #[test]
+    fn push_pop_arena_pairing_heap() {
+        for pairing in [
+            Pairing::Multipass,
+            Pairing::TwoPass,
+            Pairing::TwoPassReverse,
+        ] {
+            let n = 10000;
+            let mut highest_min = 0;
+            let mut dijkstra: Search<ArenaPairingHeap> = Search::from((Vertex(1), n));
+            dijkstra.queue = ArenaPairingHeap::with_pairing(Vertex(1), pairing);
+            let mut rng = thread_rng();
+            //push
+            for i in 1..n {
+                let to = Vertex::try_from(i).unwrap();
+                dijkstra.explore(
+                    Vertex(1),
+                    0,
+                    &Neighbor {
+                        weight: rng.gen_range(1..1000000),
+                        to,
+                    },
+                );
+            }
+            //pop some to build up trees that decrease_key has to unlink from
+            let mut popped = Vec::new();
+            for _ in 0..n / 10 {
+                let (key, value) = dijkstra.pop_min().unwrap();
+                assert!(key >= highest_min);
+                highest_min = key;
+                popped.push(value);
+            }
+            //decrease_key
+            for _ in 0..n {
+                let to: Vertex = rng.gen_range(1..n).try_into().unwrap();
+                if popped.contains(&to) {
+                    continue;
+                }
+                let (_, key, _) = dijkstra.meta.get(&to).unwrap();
+                let key = u32::max(key / 2, highest_min);
+                dijkstra.explore(Vertex(1), 0, &Neighbor { weight: key, to });
+            }
+            //pop
+            for _ in n / 10..n {
+                let (key, popped) = dijkstra.pop_min().unwrap();
+                let (_, stored_key, _) = dijkstra.meta.get(&popped).unwrap();
+                assert_eq!(key, *stored_key);
+                assert!(key >= highest_min);
+                highest_min = u32::max(highest_min, key);
+            }
+            assert_eq!(None, dijkstra.pop_min());
         }
     }
 }
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+use nohash_hasher::NoHashHasher;
+
+use crate::dijkstra::{
+    assert_send, Backend, DecreaseKey, InitDijkstra, Item, OwnedLookup, PriorityQueue,
+};
+use crate::dimacs::Vertex;
+
+/// Number of buckets, one for keys equal to the last popped key and one per bit of a key.
+const BUCKETS: usize = u32::BITS as usize + 1;
+
+/// Represents a radix heap for monotone integer keys.
+///
+/// Items are kept in buckets by the highest bit in which their key differs from the last popped
+/// key. Popping from an empty first bucket redistributes the smallest non-empty bucket, after
+/// which every item has moved closer to the first bucket, so each item is moved at most 32 times.
+/// Pushed keys must not be smaller than the last popped key, which holds for Dijkstra.
+pub struct RadixHeap {
+    buckets: [Vec<Item>; BUCKETS],
+    /// Position of every item as (bucket, index in bucket).
+    lookup: HashMap<Vertex, (usize, usize), BuildHasherDefault<NoHashHasher<u32>>>,
+    /// The last popped key.
+    last: u32,
+    len: usize,
+}
+
+impl From<Vertex> for RadixHeap {
+    #[inline]
+    fn from(value: Vertex) -> Self {
+        let mut heap = Self {
+            buckets: std::array::from_fn(|_| Vec::new()),
+            lookup: HashMap::default(),
+            last: 0,
+            len: 0,
+        };
+        heap.push(0, value);
+        heap
+    }
+}
+
+impl RadixHeap {
+    #[inline]
+    fn bucket(&self, key: u32) -> usize {
+        (u32::BITS - (key ^ self.last).leading_zeros()) as usize
+    }
+
+    #[inline]
+    fn insert(&mut self, item: Item) {
+        let bucket = self.bucket(item.key);
+        self.lookup
+            .insert(item.value, (bucket, self.buckets[bucket].len()));
+        self.buckets[bucket].push(item);
+    }
+
+    #[inline]
+    fn remove(&mut self, bucket: usize, index: usize) -> Item {
+        let item = self.buckets[bucket].swap_remove(index);
+        if let Some(moved) = self.buckets[bucket].get(index) {
+            self.lookup.insert(moved.value, (bucket, index));
+        }
+        item
+    }
+}
+
+impl PriorityQueue for RadixHeap {
+    type RefType = Vertex;
+    type Key = u32;
+    type Value = Vertex;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
+        if self.is_empty() {
+            return None;
+        }
+        if self.buckets[0].is_empty() {
+            let bucket = (1..BUCKETS).find(|&b| !self.buckets[b].is_empty())?;
+            // the new minimum splits the bucket into smaller ones
+            let mut items = std::mem::take(&mut self.buckets[bucket]);
+            self.last = items.iter().map(|item| item.key).min()?;
+            for item in items.drain(..) {
+                self.insert(item);
+            }
+            self.buckets[bucket] = items;
+        }
+        let min = self.buckets[0].pop()?;
+        self.lookup.remove(&min.value);
+        self.len -= 1;
+        Some((min.key, min.value))
+    }
+
+    #[inline]
+    fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
+        debug_assert!(key >= self.last, "keys have to be monotone");
+        self.insert(Item { key, value });
+        self.len += 1;
+        value
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        for bucket in self.buckets.iter_mut() {
+            bucket.clear();
+        }
+        self.lookup.clear();
+        self.last = 0;
+        self.len = 0;
+        self.push(0, source);
+    }
+}
+
+impl DecreaseKey for RadixHeap {
+    #[inline]
+    fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
+        let (bucket, index) = self.lookup[&of];
+        let mut item = self.remove(bucket, index);
+        item.key = key;
+        self.insert(item);
+    }
+}
+
+impl InitDijkstra for RadixHeap {
+    type Data<B: Backend> = OwnedLookup<Self, B>;
+}
+
+const _: () = assert_send::<RadixHeap>();
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{Dijkstra, Neighbor};
+
+    #[test]
+    fn monotone_keys() {
+        let mut heap = RadixHeap::from(Vertex(1));
+        heap.push(7, Vertex(2));
+        heap.push(5, Vertex(3));
+        heap.push(1 << 20, Vertex(4));
+        assert_eq!(heap.pop(), Some((0, Vertex(1))));
+        heap.decrease_key(Vertex(2), 5);
+        heap.push(6, Vertex(5));
+        let (key, _) = heap.pop().unwrap();
+        assert_eq!(key, 5);
+        let (key, _) = heap.pop().unwrap();
+        assert_eq!(key, 5);
+        heap.decrease_key(Vertex(4), 6);
+        assert_eq!(heap.pop().map(|(key, _)| key), Some(6));
+        assert_eq!(heap.pop().map(|(key, _)| key), Some(6));
+        assert_eq!(heap.pop(), None);
+        heap.reset(Vertex(9));
+        assert_eq!(heap.pop(), Some((0, Vertex(9))));
+    }
+
+    #[test]
+    fn push_pop_radix_heap() {
+        let n = 10000;
+        let mut highest_min = 0;
+        let mut dijkstra: OwnedLookup<RadixHeap> = OwnedLookup::from((Vertex(1), n));
+        let mut rng = thread_rng();
+        //push
+        for i in 1..n {
+            let to = Vertex::try_from(i).unwrap();
+            dijkstra.explore(
+                Vertex(1),
+                0,
+                &Neighbor {
+                    weight: rng.gen_range(1..1000000),
+                    to,
+                },
+            );
+        }
+        //decrease_key
+        for _ in 0..n {
+            let to: Vertex = rng.gen_range(1..n).try_into().unwrap();
+            let (key, _) = dijkstra.meta.get(&to).unwrap();
+            let key = key / 2;
+            dijkstra.explore(Vertex(1), 0, &Neighbor { weight: key, to });
+        }
+        //pop
+        for _ in 0..n {
+            let (key, popped) = dijkstra.pop_min().unwrap();
+            let (stored_key, _) = dijkstra.meta.get(&popped).unwrap();
+            assert_eq!(key, *stored_key);
+            assert!(key >= highest_min);
+            highest_min = u32::max(highest_min, key);
+        }
+        assert_eq!(dijkstra.pop_min(), None);
+    }
+}
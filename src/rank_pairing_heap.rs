@@ -0,0 +1,314 @@
+use crate::dijkstra::{
+    assert_send, Backend, DecreaseKey, Handle, InitDijkstra, PriorityQueue, Search,
+};
+use crate::dimacs::Vertex;
+
+/// Marks a missing link.
+const NIL: u32 = u32::MAX;
+
+/// Represents a node in the rank-pairing heap.
+///
+/// Trees are stored as half-trees in binary form: the left link points to the first child and
+/// the right link to the next sibling, roots have no right sibling.
+#[derive(Debug)]
+struct Node {
+    key: u32,
+    value: Vertex,
+    /// The node whose left or right link points to this one.
+    parent: u32,
+    left: u32,
+    right: u32,
+    rank: i32,
+}
+
+/// Represents a type-1 rank-pairing heap whose nodes are stored in an arena and referenced by
+/// index, as proposed by Haeupler, Sen and Tarjan.
+///
+/// Nodes are never reused until the heap is reset, so handles of popped items stay unique.
+pub struct RankPairingHeap {
+    nodes: Vec<Node>,
+    roots: Vec<u32>,
+    /// The root with the minimum key.
+    min: u32,
+    len: usize,
+    /// Root of each rank during the one-pass linking.
+    ranks: Vec<u32>,
+}
+
+impl From<Vertex> for RankPairingHeap {
+    #[inline]
+    fn from(value: Vertex) -> Self {
+        let mut heap = Self {
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            min: NIL,
+            len: 0,
+            ranks: Vec::new(),
+        };
+        heap.push(0, value);
+        heap
+    }
+}
+
+impl RankPairingHeap {
+    #[inline]
+    fn node(&self, id: u32) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    #[inline]
+    fn node_mut(&mut self, id: u32) -> &mut Node {
+        &mut self.nodes[id as usize]
+    }
+
+    /// Rank of a node, -1 if it is missing.
+    #[inline]
+    fn rank(&self, id: u32) -> i32 {
+        if id == NIL {
+            -1
+        } else {
+            self.node(id).rank
+        }
+    }
+
+    /// Turns `id` with its left subtree into a half-tree and adds it to the roots.
+    #[inline]
+    fn add_root(&mut self, id: u32) {
+        let rank = self.rank(self.node(id).left) + 1;
+        let node = self.node_mut(id);
+        node.parent = NIL;
+        node.right = NIL;
+        node.rank = rank;
+        self.roots.push(id);
+        if self.min == NIL || self.node(id).key < self.node(self.min).key {
+            self.min = id;
+        }
+    }
+
+    /// Links two half-trees of equal rank, returning the new root.
+    #[inline]
+    fn link(&mut self, a: u32, b: u32) -> u32 {
+        let (winner, loser) = if self.node(a).key <= self.node(b).key {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let child = self.node(winner).left;
+        if child != NIL {
+            self.node_mut(child).parent = loser;
+        }
+        let node = self.node_mut(loser);
+        node.right = child;
+        node.parent = winner;
+        let node = self.node_mut(winner);
+        node.left = loser;
+        node.rank += 1;
+        winner
+    }
+}
+
+impl PriorityQueue for RankPairingHeap {
+    type RefType = Handle;
+    type Key = u32;
+    type Value = Vertex;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<(Self::Key, Self::Value)> {
+        if self.min == NIL {
+            return None;
+        }
+        let min = self.min;
+        let mut candidates = std::mem::take(&mut self.roots);
+        candidates.retain(|&root| root != min);
+        // the right spine of the left child holds the children of the minimum
+        let mut child = self.node(min).left;
+        while child != NIL {
+            let next = self.node(child).right;
+            let rank = self.rank(self.node(child).left) + 1;
+            let node = self.node_mut(child);
+            node.parent = NIL;
+            node.right = NIL;
+            node.rank = rank;
+            candidates.push(child);
+            child = next;
+        }
+        // link each pair of equal rank once
+        self.min = NIL;
+        for root in candidates {
+            let rank = self.node(root).rank as usize;
+            if rank >= self.ranks.len() {
+                self.ranks.resize(rank + 1, NIL);
+            }
+            match std::mem::replace(&mut self.ranks[rank], NIL) {
+                NIL => self.ranks[rank] = root,
+                other => {
+                    let linked = self.link(root, other);
+                    self.add_root(linked);
+                }
+            }
+        }
+        for rank in 0..self.ranks.len() {
+            let root = std::mem::replace(&mut self.ranks[rank], NIL);
+            if root != NIL {
+                self.add_root(root);
+            }
+        }
+        self.len -= 1;
+        let node = self.node(min);
+        Some((node.key, node.value))
+    }
+
+    #[inline]
+    fn push(&mut self, key: Self::Key, value: Self::Value) -> Self::RefType {
+        let id = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            key,
+            value,
+            parent: NIL,
+            left: NIL,
+            right: NIL,
+            rank: 0,
+        });
+        self.add_root(id);
+        self.len += 1;
+        Handle(id)
+    }
+
+    #[inline]
+    fn reset(&mut self, source: Vertex) {
+        self.nodes.clear();
+        self.roots.clear();
+        self.min = NIL;
+        self.len = 0;
+        self.push(0, source);
+    }
+}
+
+impl DecreaseKey for RankPairingHeap {
+    #[inline]
+    fn decrease_key(&mut self, of: Self::RefType, key: Self::Key) {
+        let id = of.0;
+        self.node_mut(id).key = key;
+        let parent = self.node(id).parent;
+        if parent == NIL {
+            if key < self.node(self.min).key {
+                self.min = id;
+            }
+            return;
+        }
+        // replace the node by its right subtree and make it a root
+        let right = self.node(id).right;
+        if self.node(parent).left == id {
+            self.node_mut(parent).left = right;
+        } else {
+            self.node_mut(parent).right = right;
+        }
+        if right != NIL {
+            self.node_mut(right).parent = parent;
+        }
+        self.add_root(id);
+        // restore the type-1 rank rule along the path to the root
+        let mut node = parent;
+        loop {
+            let (left, right) = (self.node(node).left, self.node(node).right);
+            let rank = if self.node(node).parent == NIL {
+                self.rank(left) + 1
+            } else {
+                let (a, b) = (self.rank(left), self.rank(right));
+                if (a - b).abs() > 1 {
+                    i32::max(a, b)
+                } else {
+                    i32::max(a, b) + 1
+                }
+            };
+            if rank >= self.node(node).rank {
+                break;
+            }
+            self.node_mut(node).rank = rank;
+            node = self.node(node).parent;
+            if node == NIL {
+                break;
+            }
+        }
+    }
+}
+
+impl InitDijkstra for RankPairingHeap {
+    type Data<B: Backend> = Search<Self, B>;
+}
+
+const _: () = assert_send::<RankPairingHeap>();
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{Dijkstra, Neighbor};
+
+    #[test]
+    fn simple_merge() {
+        let mut heap = RankPairingHeap::from(Vertex(1));
+        heap.push(2, Vertex(2));
+        let this = heap.push(4, Vertex(3));
+        assert_eq!(heap.pop(), Some((0, Vertex(1))));
+        heap.decrease_key(this, 1);
+        assert_eq!(heap.pop(), Some((1, Vertex(3))));
+        assert_eq!(heap.pop(), Some((2, Vertex(2))));
+        assert_eq!(heap.pop(), None);
+        heap.reset(Vertex(9));
+        assert_eq!(heap.pop(), Some((0, Vertex(9))));
+    }
+
+    #[test]
+    fn push_pop_rank_pairing_heap() {
+        let n = 10000;
+        let mut highest_min = 0;
+        let mut dijkstra: Search<RankPairingHeap> = Search::from((Vertex(1), n));
+        let mut rng = thread_rng();
+        //push
+        for i in 1..n {
+            let to = Vertex::try_from(i).unwrap();
+            dijkstra.explore(
+                Vertex(1),
+                0,
+                &Neighbor {
+                    weight: rng.gen_range(1..1000000),
+                    to,
+                },
+            );
+        }
+        //pop some to build up trees that decrease_key has to cut
+        let mut popped = Vec::new();
+        for _ in 0..n / 10 {
+            let (key, value) = dijkstra.pop_min().unwrap();
+            assert!(key >= highest_min);
+            highest_min = key;
+            popped.push(value);
+        }
+        //decrease_key
+        for _ in 0..n {
+            let to: Vertex = rng.gen_range(1..n).try_into().unwrap();
+            if popped.contains(&to) {
+                continue;
+            }
+            let (_, key, _) = dijkstra.meta.get(&to).unwrap();
+            let key = u32::max(key / 2, highest_min);
+            dijkstra.explore(Vertex(1), 0, &Neighbor { weight: key, to });
+        }
+        //pop
+        for _ in n / 10..n {
+            let (key, popped) = dijkstra.pop_min().unwrap();
+            let (_, stored_key, _) = dijkstra.meta.get(&popped).unwrap();
+            assert_eq!(key, *stored_key);
+            assert!(key >= highest_min);
+            highest_min = u32::max(highest_min, key);
+        }
+        assert_eq!(None, dijkstra.pop_min());
+    }
+}
@@ -0,0 +1,448 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::dijkstra::PriorityQueue;
+use crate::dimacs::{DualEdge, Route, Vertex};
+
+/// Denominator of the Lagrangian multiplier, which is kept as a fraction to stay in integers.
+const LAGRANGIAN_SCALE: u64 = 1 << 10;
+/// Number of bisection steps used to approximate the optimal Lagrangian multiplier.
+const LAGRANGIAN_STEPS: usize = 24;
+/// Largest multiplier tried, as a multiple of `LAGRANGIAN_SCALE`.
+const LAGRANGIAN_LIMIT: u64 = 1 << 16;
+
+/// Represents a neighboring vertex with the cost and resource consumption of reaching it.
+#[derive(Clone, Copy, Debug)]
+pub struct ResourceNeighbor {
+    pub to: Vertex,
+    pub weight: u32,
+    pub resource: u32,
+}
+
+/// Represents a graph whose edges have a cost and consume a second resource.
+pub struct ResourceGraph {
+    pub forward: Vec<Vec<ResourceNeighbor>>,
+    pub backward: Vec<Vec<ResourceNeighbor>>,
+}
+
+impl ResourceGraph {
+    pub fn new(n: usize, edges: impl Iterator<Item = DualEdge>) -> Self {
+        let mut forward = vec![Vec::new(); n];
+        let mut backward = vec![Vec::new(); n];
+        for e in edges {
+            forward[usize::from(e.from)].push(ResourceNeighbor {
+                to: e.to,
+                weight: e.weight,
+                resource: e.resource,
+            });
+            backward[usize::from(e.to)].push(ResourceNeighbor {
+                to: e.from,
+                weight: e.weight,
+                resource: e.resource,
+            });
+        }
+        Self { forward, backward }
+    }
+}
+
+/// A partial path ending at `vertex`.
+struct Label {
+    vertex: Vertex,
+    cost: u32,
+    resource: u32,
+    /// Label this one was extended from, `None` for the source.
+    pred: Option<usize>,
+    /// Set once another label at the same vertex dominates this one.
+    dominated: bool,
+}
+
+/// Distances of all vertices to a target and the next vertex on the way there.
+/// `u64::MAX` marks vertices that cannot reach the target.
+struct ToTarget {
+    dist: Vec<u64>,
+    next: Vec<Vertex>,
+}
+
+impl ToTarget {
+    /// Runs a backward Dijkstra from `target` with the combined edge weight `weight`.
+    fn new(
+        graph: &ResourceGraph,
+        target: Vertex,
+        weight: impl Fn(&ResourceNeighbor) -> u64,
+    ) -> Self {
+        let n = graph.backward.len();
+        let mut dist = vec![u64::MAX; n];
+        let mut next = vec![target; n];
+        let mut queue = BinaryHeap::new();
+        dist[usize::from(target)] = 0;
+        queue.push(Reverse((0, usize::from(target))));
+        while let Some(Reverse((d, v))) = queue.pop() {
+            if d > dist[v] {
+                continue;
+            }
+            for e in graph.backward[v].iter() {
+                let u = usize::from(e.to);
+                let alt = d.saturating_add(weight(e));
+                if alt < dist[u] {
+                    dist[u] = alt;
+                    next[u] = Vertex::try_from(v).unwrap();
+                    queue.push(Reverse((alt, u)));
+                }
+            }
+        }
+        Self { dist, next }
+    }
+
+    #[inline]
+    fn get(&self, v: Vertex) -> u64 {
+        self.dist[usize::from(v)]
+    }
+
+    /// Follows the tree from `source` and returns the cost, resource and route of the path.
+    fn path(&self, graph: &ResourceGraph, source: Vertex) -> Option<(u32, u32, Route)> {
+        if self.get(source) == u64::MAX {
+            return None;
+        }
+        let (mut cost, mut resource) = (0, 0);
+        let mut path = vec![source];
+        let mut head = source;
+        while self.get(head) != 0 {
+            let to = self.next[usize::from(head)];
+            let e = graph.forward[usize::from(head)]
+                .iter()
+                .filter(|e| e.to == to)
+                .min_by_key(|e| (e.weight, e.resource))
+                .unwrap();
+            cost += e.weight;
+            resource += e.resource;
+            path.push(to);
+            head = to;
+        }
+        path.reverse();
+        Some((cost, resource, Route(path)))
+    }
+}
+
+/// Lagrangian relaxation of the resource constraint for a multiplier `factor / LAGRANGIAN_SCALE`.
+struct Lagrangian {
+    factor: u64,
+    bound: ToTarget,
+}
+
+impl Lagrangian {
+    /// Computes the value of the relaxation scaled by `LAGRANGIAN_SCALE`.
+    #[inline]
+    fn value(&self, source: Vertex, budget: u32) -> i128 {
+        self.bound.get(source) as i128 - (self.factor * budget as u64) as i128
+    }
+
+    /// Checks whether every completion of a label is at least as expensive as `upper`.
+    #[inline]
+    fn prunes(&self, label: &Label, budget: u32, upper: u32) -> bool {
+        let rest = self.bound.get(label.vertex);
+        if rest == u64::MAX {
+            return true;
+        }
+        let scaled = (LAGRANGIAN_SCALE * label.cost as u64) as i128 + rest as i128
+            - (self.factor * (budget - label.resource) as u64) as i128;
+        scaled > (LAGRANGIAN_SCALE as i128) * (upper as i128 - 1)
+    }
+}
+
+/// Searches the multiplier maximizing the Lagrangian lower bound by bisection and returns it
+/// together with the cheapest feasible route encountered on the way.
+fn lagrangian(
+    graph: &ResourceGraph,
+    source: Vertex,
+    target: Vertex,
+    budget: u32,
+) -> (Lagrangian, Option<(u32, u32, Route)>) {
+    let evaluate = |factor: u64| {
+        let bound = ToTarget::new(graph, target, |e| {
+            LAGRANGIAN_SCALE * e.weight as u64 + factor * e.resource as u64
+        });
+        let path = bound.path(graph, source);
+        (Lagrangian { factor, bound }, path)
+    };
+    let mut incumbent: Option<(u32, u32, Route)> = None;
+    let mut consider = |path: Option<(u32, u32, Route)>| -> bool {
+        match path {
+            Some((cost, resource, route)) if resource <= budget => {
+                if incumbent.as_ref().is_none_or(|(best, _, _)| cost < *best) {
+                    incumbent = Some((cost, resource, route));
+                }
+                true
+            }
+            _ => false,
+        }
+    };
+
+    let (mut best, path) = evaluate(0);
+    if consider(path) {
+        // the cheapest route is feasible, nothing to relax
+        return (best, incumbent);
+    }
+    let (mut low, mut high) = (0, LAGRANGIAN_SCALE);
+    loop {
+        let (relaxed, path) = evaluate(high);
+        if relaxed.value(source, budget) > best.value(source, budget) {
+            best = relaxed;
+        }
+        if consider(path) || high >= LAGRANGIAN_SCALE * LAGRANGIAN_LIMIT {
+            break;
+        }
+        low = high;
+        high *= 2;
+    }
+    for _ in 0..LAGRANGIAN_STEPS {
+        if high - low <= 1 {
+            break;
+        }
+        let mid = (low + high) / 2;
+        let (relaxed, path) = evaluate(mid);
+        if relaxed.value(source, budget) > best.value(source, budget) {
+            best = relaxed;
+        }
+        if consider(path) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    (best, incumbent)
+}
+
+/// Performs resource-constrained shortest path computation.
+///
+/// Returns the cost, the resource consumption and the cheapest route from `source` to
+/// `target` that consumes at most `budget`, or `None` if no such route exists.
+/// Partial paths are kept as labels that are pruned by dominance and by lower bounds on the
+/// cost and resource still needed to reach the target.
+///
+/// # Arguments
+///
+/// * `graph` - Graph with dual edge metrics.
+/// * `lagrangian` - Additionally prune with the bound of a Lagrangian relaxation of the budget.
+pub fn rcsp<Q>(
+    graph: &ResourceGraph,
+    source: Vertex,
+    target: Vertex,
+    budget: u32,
+    lagrangian: bool,
+) -> Option<(u32, u32, Route)>
+where
+    Q: PriorityQueue,
+{
+    let to_cost = ToTarget::new(graph, target, |e| e.weight as u64);
+    let to_resource = ToTarget::new(graph, target, |e| e.resource as u64);
+    if to_resource.get(source) > budget as u64 {
+        return None;
+    }
+    let (relaxation, mut incumbent) = if lagrangian {
+        let (relaxation, incumbent) = self::lagrangian(graph, source, target, budget);
+        (Some(relaxation), incumbent)
+    } else {
+        (None, None)
+    };
+
+    let mut labels = vec![Label {
+        vertex: source,
+        cost: 0,
+        resource: 0,
+        pred: None,
+        dominated: false,
+    }];
+    let mut pareto: Vec<Vec<usize>> = vec![Vec::new(); graph.forward.len()];
+    pareto[usize::from(source)].push(0);
+    let mut queue = Q::from(Vertex::try_from(0usize).unwrap());
+
+    while let Some((_, id)) = queue.pop() {
+        let id = usize::from(id.into());
+        let label = &labels[id];
+        if label.dominated {
+            continue;
+        }
+        if label.vertex == target {
+            let (cost, resource) = (label.cost, label.resource);
+            let mut path = Vec::new();
+            let mut head = Some(id);
+            while let Some(l) = head {
+                path.push(labels[l].vertex);
+                head = labels[l].pred;
+            }
+            return Some((cost, resource, Route(path)));
+        }
+        let (vertex, cost, resource) = (label.vertex, label.cost, label.resource);
+        for e in graph.forward[usize::from(vertex)].iter() {
+            let extended = Label {
+                vertex: e.to,
+                cost: cost + e.weight,
+                resource: resource + e.resource,
+                pred: Some(id),
+                dominated: false,
+            };
+            let rest_resource = to_resource.get(e.to);
+            if rest_resource == u64::MAX || extended.resource as u64 + rest_resource > budget as u64
+            {
+                continue;
+            }
+            let key = extended.cost as u64 + to_cost.get(e.to);
+            if let Some((upper, _, _)) = incumbent {
+                if key >= upper as u64 {
+                    continue;
+                }
+                if let Some(relaxation) = &relaxation {
+                    if relaxation.prunes(&extended, budget, upper) {
+                        continue;
+                    }
+                }
+            }
+            let front = &mut pareto[usize::from(e.to)];
+            if front.iter().any(|&other| {
+                labels[other].cost <= extended.cost && labels[other].resource <= extended.resource
+            }) {
+                continue;
+            }
+            front.retain(|&other| {
+                let worse = labels[other].cost >= extended.cost
+                    && labels[other].resource >= extended.resource;
+                labels[other].dominated |= worse;
+                !worse
+            });
+            front.push(labels.len());
+            queue.push(
+                (key as u32).into(),
+                Vertex::try_from(labels.len()).unwrap().into(),
+            );
+            labels.push(extended);
+        }
+    }
+    // the search only fails to improve on a route found by the relaxation
+    incumbent.take()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::implicit_heaps::PentaryHeapSimple;
+
+    /// Cheapest cost to reach every vertex with at most `r` resource, for all `r <= budget`.
+    fn exhaustive(n: usize, edges: &[DualEdge], source: Vertex, budget: u32) -> Vec<Vec<u32>> {
+        let mut best = vec![vec![u32::MAX; n]; budget as usize + 1];
+        for r in 0..=budget as usize {
+            if r > 0 {
+                best[r] = best[r - 1].clone();
+            }
+            best[r][usize::from(source)] = 0;
+            for e in edges.iter() {
+                let used = e.resource as usize;
+                if used <= r && best[r - used][usize::from(e.from)] != u32::MAX {
+                    let alt = best[r - used][usize::from(e.from)] + e.weight;
+                    let slot = &mut best[r][usize::from(e.to)];
+                    *slot = u32::min(*slot, alt);
+                }
+            }
+        }
+        best
+    }
+
+    /// Sums up cost and resource along a route given from target to source.
+    fn walk(route: &Route, edges: &[DualEdge]) -> (u32, u32) {
+        let (mut cost, mut resource) = (0, 0);
+        for pair in route.0.windows(2) {
+            let e = edges
+                .iter()
+                .filter(|e| e.from == pair[1] && e.to == pair[0])
+                .min_by_key(|e| (e.weight, e.resource))
+                .unwrap();
+            cost += e.weight;
+            resource += e.resource;
+        }
+        (cost, resource)
+    }
+
+    fn random_dual_edges(n: usize, m: usize) -> Vec<DualEdge> {
+        let mut rng = thread_rng();
+        (0..m)
+            .map(|_| {
+                let weight = rng.gen_range(1..100);
+                DualEdge {
+                    from: Vertex::try_from(rng.gen_range(0..n)).unwrap(),
+                    to: Vertex::try_from(rng.gen_range(0..n)).unwrap(),
+                    weight,
+                    // cheap arcs tend to consume more
+                    resource: rng.gen_range(1..=10) + (100 - weight) / 20,
+                }
+            })
+            .collect()
+    }
+
+    fn compare(lagrangian: bool) {
+        let n = 40;
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            let edges = random_dual_edges(n, 6 * n);
+            let graph = ResourceGraph::new(n, edges.clone().into_iter());
+            let source = Vertex::try_from(rng.gen_range(0..n)).unwrap();
+            let budget = rng.gen_range(5..60);
+            let best = exhaustive(n, &edges, source, budget);
+            for (target, &expected) in best[budget as usize].iter().enumerate() {
+                let target = Vertex::try_from(target).unwrap();
+                match rcsp::<PentaryHeapSimple>(&graph, source, target, budget, lagrangian) {
+                    Some((cost, resource, route)) => {
+                        assert_eq!(cost, expected);
+                        assert!(resource <= budget);
+                        assert_eq!(route.0.first(), Some(&target));
+                        assert_eq!(route.0.last(), Some(&source));
+                        assert!(walk(&route, &edges).0 <= cost);
+                    }
+                    None => assert_eq!(expected, u32::MAX),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rcsp_labels() {
+        compare(false);
+    }
+
+    #[test]
+    fn rcsp_lagrangian() {
+        compare(true);
+    }
+
+    #[test]
+    fn rcsp_budget() {
+        // 1 -> 3 is fast but long, 1 -> 2 -> 3 is slow but short
+        let edges = vec![
+            DualEdge {
+                from: Vertex(1),
+                to: Vertex(3),
+                weight: 1,
+                resource: 10,
+            },
+            DualEdge {
+                from: Vertex(1),
+                to: Vertex(2),
+                weight: 5,
+                resource: 2,
+            },
+            DualEdge {
+                from: Vertex(2),
+                to: Vertex(3),
+                weight: 5,
+                resource: 2,
+            },
+        ];
+        let graph = ResourceGraph::new(3, edges.into_iter());
+        let fast = rcsp::<PentaryHeapSimple>(&graph, Vertex(1), Vertex(3), 10, true).unwrap();
+        assert_eq!((fast.0, fast.1), (1, 10));
+        let short = rcsp::<PentaryHeapSimple>(&graph, Vertex(1), Vertex(3), 9, true).unwrap();
+        assert_eq!((short.0, short.1), (10, 4));
+        assert_eq!(short.2 .0, vec![Vertex(3), Vertex(2), Vertex(1)]);
+        assert!(rcsp::<PentaryHeapSimple>(&graph, Vertex(1), Vertex(3), 3, false).is_none());
+    }
+}
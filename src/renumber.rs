@@ -0,0 +1,403 @@
+use std::collections::VecDeque;
+
+use crate::dijkstra::{
+    sp_bi, sp_naiv, sssp, DicirectionalList, Dijkstra, Graph, Hashed, InitDijkstra, Neighbor,
+    NeighborList,
+};
+use crate::dimacs::{Coordinates, Route, Vertex, UNDEFINED};
+
+/// Represents a mapping between the vertices of a graph and those of a graph derived from it
+/// by renumbering or dropping vertices.
+#[derive(Clone, Debug)]
+pub struct Renumbering {
+    /// New id of every original vertex, `UNDEFINED` if it was dropped.
+    to_new: Vec<Vertex>,
+    /// Original id of every new vertex.
+    to_original: Vec<Vertex>,
+}
+
+impl Renumbering {
+    /// Constructs a mapping that keeps all `n` vertices as they are.
+    pub fn identity(n: usize) -> Self {
+        let ids: Vec<Vertex> = (0..n).map(|i| Vertex::try_from(i).unwrap()).collect();
+        Self {
+            to_new: ids.clone(),
+            to_original: ids,
+        }
+    }
+
+    /// Constructs a mapping from the new order of the vertices of a graph with `n` vertices.
+    /// The `i`-th vertex of `order` becomes the `i`-th vertex, vertices not listed are dropped.
+    pub fn from_order(order: Vec<Vertex>, n: usize) -> Self {
+        let mut to_new = vec![UNDEFINED; n];
+        for (i, v) in order.iter().enumerate() {
+            to_new[usize::from(*v)] = Vertex::try_from(i).unwrap();
+        }
+        Self {
+            to_new,
+            to_original: order,
+        }
+    }
+
+    /// Number of vertices of the derived graph.
+    #[inline]
+    pub fn num_vertices(&self) -> usize {
+        self.to_original.len()
+    }
+
+    /// Gets the new id of an original vertex, `None` if it was dropped.
+    #[inline]
+    pub fn to_new(&self, v: Vertex) -> Option<Vertex> {
+        let new = self.to_new[usize::from(v)];
+        (new != UNDEFINED).then_some(new)
+    }
+
+    /// Gets the original id of a new vertex.
+    #[inline]
+    pub fn to_original(&self, v: Vertex) -> Vertex {
+        self.to_original[usize::from(v)]
+    }
+
+    /// Translates a route of the derived graph back to the original vertices.
+    #[inline]
+    pub fn route_to_original(&self, route: Route) -> Route {
+        Route(route.0.into_iter().map(|v| self.to_original(v)).collect())
+    }
+
+    /// Builds the derived graph, dropping the arcs of dropped vertices.
+    pub fn apply<G: Graph>(&self, graph: &G) -> NeighborList {
+        self.to_original
+            .iter()
+            .map(|&u| {
+                graph
+                    .get_neighbors(u)
+                    .filter_map(|e| {
+                        Some(Neighbor {
+                            to: self.to_new(e.to)?,
+                            weight: e.weight,
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Builds both directions of the derived graph, dropping the arcs of dropped vertices.
+    pub fn apply_bidirectional<G: Graph>(
+        &self,
+        graph: &DicirectionalList<G>,
+    ) -> DicirectionalList<NeighborList> {
+        DicirectionalList {
+            forward: self.apply(&graph.forward),
+            backward: self.apply(&graph.backward),
+        }
+    }
+}
+
+/// Orders the vertices by a breadth first search from `root`, so vertices close in the graph get
+/// close ids. Vertices unreachable from `root` follow in the order of their own searches.
+pub fn bfs_order<G: Graph>(graph: &G, root: Vertex) -> Renumbering {
+    let n = graph.num_vertices();
+    let mut visited = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let mut queue = VecDeque::new();
+    let roots = std::iter::once(usize::from(root)).chain(0..n);
+    for r in roots {
+        if visited[r] {
+            continue;
+        }
+        visited[r] = true;
+        queue.push_back(Vertex::try_from(r).unwrap());
+        while let Some(u) = queue.pop_front() {
+            order.push(u);
+            for e in graph.get_neighbors(u) {
+                if !visited[usize::from(e.to)] {
+                    visited[usize::from(e.to)] = true;
+                    queue.push_back(e.to);
+                }
+            }
+        }
+    }
+    Renumbering::from_order(order, n)
+}
+
+/// Orders the vertices by a depth first search from `root` in preorder, so paths of the graph
+/// get consecutive ids. Vertices unreachable from `root` follow in the order of their own searches.
+pub fn dfs_order<G: Graph>(graph: &G, root: Vertex) -> Renumbering {
+    let n = graph.num_vertices();
+    let mut visited = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let roots = std::iter::once(usize::from(root)).chain(0..n);
+    for r in roots {
+        if visited[r] {
+            continue;
+        }
+        visited[r] = true;
+        let first = Vertex::try_from(r).unwrap();
+        order.push(first);
+        let mut frames = vec![graph.get_neighbors(first)];
+        while let Some(neighbors) = frames.last_mut() {
+            match neighbors.next() {
+                Some(e) if !visited[usize::from(e.to)] => {
+                    visited[usize::from(e.to)] = true;
+                    order.push(e.to);
+                    frames.push(graph.get_neighbors(e.to));
+                }
+                Some(_) => {}
+                None => {
+                    frames.pop();
+                }
+            }
+        }
+    }
+    Renumbering::from_order(order, n)
+}
+
+/// Orders the vertices along a Hilbert curve through their coordinates, so vertices close in
+/// the plane get close ids.
+///
+/// # Arguments
+///
+/// * `coordinates` - Coordinates of all vertices in order, as read by `load_coordinates`.
+pub fn hilbert_order(coordinates: &[Coordinates]) -> Renumbering {
+    const BITS: u32 = 16;
+    let n = coordinates.len();
+    let (min_x, max_x) = bounds(coordinates.iter().map(|c| c.x));
+    let (min_y, max_y) = bounds(coordinates.iter().map(|c| c.y));
+    // scale both axes onto a grid of 2^BITS cells
+    let scale = |value: i64, min: i64, max: i64| {
+        let span = i128::max((max - min) as i128, 1);
+        (((value - min) as i128 * ((1 << BITS) - 1)) / span) as u32
+    };
+    let mut keys: Vec<(u64, Vertex)> = coordinates
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let x = scale(c.x, min_x, max_x);
+            let y = scale(c.y, min_y, max_y);
+            (hilbert_index(BITS, x, y), Vertex::try_from(i).unwrap())
+        })
+        .collect();
+    keys.sort_unstable_by_key(|(key, v)| (*key, v.0));
+    Renumbering::from_order(keys.into_iter().map(|(_, v)| v).collect(), n)
+}
+
+#[inline]
+fn bounds(values: impl Iterator<Item = i64>) -> (i64, i64) {
+    values.fold((i64::MAX, i64::MIN), |(min, max), v| {
+        (i64::min(min, v), i64::max(max, v))
+    })
+}
+
+/// Computes the position of the cell `(x, y)` along the Hilbert curve through a grid of
+/// `2^bits` by `2^bits` cells.
+fn hilbert_index(bits: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0;
+    let mut s = 1 << (bits - 1);
+    while s > 0 {
+        let rx = u32::from(x & s != 0);
+        let ry = u32::from(y & s != 0);
+        index += u64::from(s) * u64::from(s) * u64::from((3 * rx) ^ ry);
+        // rotate the quadrant so the curve continues in the right direction
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x &= s - 1;
+        y &= s - 1;
+        s >>= 1;
+    }
+    index
+}
+
+/// Represents a graph stored in a cache friendly vertex order.
+///
+/// Queries take and return the original vertex ids and are translated to the internal order.
+pub struct Reordered {
+    pub graph: DicirectionalList<NeighborList>,
+    pub map: Renumbering,
+}
+
+impl Reordered {
+    /// Stores both directions of `graph` in the order given by `map`.
+    pub fn new<G: Graph>(graph: &DicirectionalList<G>, map: Renumbering) -> Self {
+        Self {
+            graph: map.apply_bidirectional(graph),
+            map,
+        }
+    }
+
+    /// Number of vertices, used to size the search structures.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.map.num_vertices()
+    }
+
+    /// Performs single-source shortest path computation with the priority queue `Q`.
+    pub fn sssp<Q: InitDijkstra>(&self, source: Vertex) -> Translated<'_, Q::Data<Hashed>> {
+        let search = Q::Data::<Hashed>::from((self.new_id(source), self.size()));
+        Translated {
+            search: sssp(search, &self.graph.forward),
+            map: &self.map,
+        }
+    }
+
+    /// Performs shortest path computation to a specific target with the priority queue `Q`.
+    pub fn sp_naiv<Q: InitDijkstra>(&self, source: Vertex, target: Vertex) -> Option<(u32, Route)> {
+        let search = Q::Data::<Hashed>::from((self.new_id(source), self.size()));
+        let (dist, route) = sp_naiv(search, self.new_id(target), &self.graph.forward)?;
+        Some((dist, self.map.route_to_original(route)))
+    }
+
+    /// Performs bidirectional shortest path computation with the priority queue `Q`.
+    pub fn sp_bi<Q: InitDijkstra>(&self, source: Vertex, target: Vertex) -> Option<(u32, Route)> {
+        let forward = Q::Data::<Hashed>::from((self.new_id(source), self.size()));
+        let backward = Q::Data::<Hashed>::from((self.new_id(target), self.size()));
+        let (dist, route) = sp_bi(forward, backward, &self.graph)?;
+        Some((dist, self.map.route_to_original(route)))
+    }
+
+    #[inline]
+    fn new_id(&self, v: Vertex) -> Vertex {
+        self.map
+            .to_new(v)
+            .expect("vertex is not part of the reordered graph")
+    }
+}
+
+/// Represents a finished search on a reordered graph, answering in original vertex ids.
+pub struct Translated<'a, D: Dijkstra> {
+    search: D,
+    map: &'a Renumbering,
+}
+
+impl<D: Dijkstra> Translated<'_, D> {
+    #[inline]
+    pub fn get_dist(&self, target: Vertex) -> Option<u32> {
+        self.search.get_dist(self.map.to_new(target)?)
+    }
+
+    #[inline]
+    pub fn get_pred(&self, target: Vertex) -> Option<Vertex> {
+        let (_, pred) = self.search.get_meta(self.map.to_new(target)?)?;
+        Some(self.map.to_original(pred.into()))
+    }
+
+    #[inline]
+    pub fn get_path(&self, target: Vertex) -> Option<Route> {
+        let route = self.search.get_path(self.map.to_new(target)?)?;
+        Some(self.map.route_to_original(route))
+    }
+
+    /// Gets the search in the internal vertex order.
+    #[inline]
+    pub fn into_inner(self) -> D {
+        self.search
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{OwnedLookup, StructuredEdges};
+    use crate::dimacs::tests::random_edges;
+    use crate::dimacs::Edge;
+    use crate::implicit_heaps::BinaryHeap;
+
+    #[test]
+    fn drop_and_translate() {
+        let edges = [(1, 2, 3), (2, 3, 4), (3, 1, 5), (3, 4, 6)].map(|(f, t, w)| Edge {
+            from: Vertex(f),
+            to: Vertex(t),
+            weight: w,
+        });
+        let graph: NeighborList = StructuredEdges::new(4, edges.into_iter());
+        let map = Renumbering::from_order(vec![Vertex(3), Vertex(1), Vertex(2)], 4);
+        assert_eq!(map.num_vertices(), 3);
+        assert_eq!(map.to_new(Vertex(3)), Some(Vertex(1)));
+        assert_eq!(map.to_new(Vertex(4)), None);
+        assert_eq!(map.to_original(Vertex(2)), Vertex(1));
+
+        let derived = map.apply(&graph);
+        // 3 -> 1 stays, 3 -> 4 is dropped with 4
+        let arcs: Vec<(Vertex, u32)> = derived
+            .get_neighbors(Vertex(1))
+            .map(|e| (e.to, e.weight))
+            .collect();
+        assert_eq!(arcs, vec![(Vertex(2), 5)]);
+        let route = map.route_to_original(Route(vec![Vertex(1), Vertex(3), Vertex(2)]));
+        assert_eq!(route.0, vec![Vertex(3), Vertex(2), Vertex(1)]);
+    }
+
+    #[test]
+    fn hilbert_curve() {
+        // the curve through a 2x2 grid visits (0, 0), (0, 1), (1, 1), (1, 0)
+        let cells = [(0, 0), (0, 1), (1, 1), (1, 0)];
+        for (i, (x, y)) in cells.into_iter().enumerate() {
+            assert_eq!(hilbert_index(1, x, y), i as u64);
+        }
+        // consecutive cells of a larger grid are neighbors
+        let mut cells: Vec<(u64, u32, u32)> = (0..16)
+            .flat_map(|x| (0..16).map(move |y| (hilbert_index(4, x, y), x, y)))
+            .collect();
+        cells.sort();
+        for (i, pair) in cells.windows(2).enumerate() {
+            assert_eq!(pair[0].0, i as u64);
+            assert_eq!(
+                pair[0].1.abs_diff(pair[1].1) + pair[0].2.abs_diff(pair[1].2),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn reordered_queries() {
+        let n = 400;
+        let edges = random_edges(n, 3 * n);
+        let graph: DicirectionalList<NeighborList> = DicirectionalList::new(n, edges.into_iter());
+        let coordinates: Vec<Coordinates> = (0..n as i64)
+            .map(|i| Coordinates {
+                x: (i * 7919) % 1000 - 500,
+                y: (i * 104729) % 1000,
+            })
+            .collect();
+        let orders = [
+            bfs_order(&graph.forward, Vertex(1)),
+            dfs_order(&graph.forward, Vertex(1)),
+            hilbert_order(&coordinates),
+        ];
+        let mut rng = thread_rng();
+        for map in orders {
+            assert_eq!(map.num_vertices(), n);
+            let reordered = Reordered::new(&graph, map);
+            let s = Vertex(rng.gen_range(1..=n as u32));
+            let expected = sssp(OwnedLookup::<BinaryHeap>::from((s, n)), &graph.forward);
+            let result = reordered.sssp::<BinaryHeap>(s);
+            for i in 0..n {
+                let v = Vertex::try_from(i).unwrap();
+                assert_eq!(
+                    result.get_dist(v),
+                    expected.get_dist(v),
+                    "distance of {}",
+                    v
+                );
+            }
+            let t = Vertex(rng.gen_range(1..=n as u32));
+            let path = result.get_path(t).unwrap();
+            assert_eq!((path.0.first(), path.0.last()), (Some(&t), Some(&s)));
+            if s == t {
+                continue;
+            }
+            let (dist, route) = reordered.sp_naiv::<BinaryHeap>(s, t).unwrap();
+            assert_eq!(Some(dist), expected.get_dist(t));
+            assert_eq!((route.0.first(), route.0.last()), (Some(&t), Some(&s)));
+            let (dist, _) = reordered.sp_bi::<BinaryHeap>(s, t).unwrap();
+            assert_eq!(Some(dist), expected.get_dist(t));
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use crate::dijkstra::{Dijkstra, Graph, Neighbor, NeighborList};
+use crate::dimacs::{Edge, Route, Turn, Vertex};
+
+/// Represents a table of turn costs and turn restrictions.
+pub struct TurnCosts {
+    /// Cost of each listed turn `(from, via, to)`, `None` if the turn is forbidden.
+    turns: HashMap<(Vertex, Vertex, Vertex), Option<u32>>,
+    /// Cost of turning back onto the reverse arc, `None` if U-turns are forbidden.
+    pub u_turn: Option<u32>,
+}
+
+impl Default for TurnCosts {
+    /// An empty table, every turn including U-turns is free.
+    #[inline]
+    fn default() -> Self {
+        Self {
+            turns: HashMap::new(),
+            u_turn: Some(0),
+        }
+    }
+}
+
+impl FromIterator<Turn> for TurnCosts {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = Turn>>(iter: I) -> Self {
+        let mut costs = Self::default();
+        for turn in iter {
+            costs.insert(turn);
+        }
+        costs
+    }
+}
+
+impl TurnCosts {
+    /// Adds a turn to the table, replacing a previous entry for the same turn.
+    #[inline]
+    pub fn insert(&mut self, turn: Turn) {
+        self.turns.insert((turn.from, turn.via, turn.to), turn.cost);
+    }
+
+    /// Gets the cost of turning from `from -> via` onto `via -> to`, `None` if forbidden.
+    /// Listed turns take precedence over the U-turn cost.
+    #[inline]
+    pub fn get(&self, from: Vertex, via: Vertex, to: Vertex) -> Option<u32> {
+        match self.turns.get(&(from, via, to)) {
+            Some(cost) => *cost,
+            None if from == to => self.u_turn,
+            None => Some(0),
+        }
+    }
+}
+
+/// Represents the line graph of a road network used for edge-based search.
+///
+/// Every arc of the original graph becomes a vertex of the line graph and every allowed turn
+/// becomes an edge weighted with the length of the arc turned onto plus the turn cost.
+/// Each original vertex additionally gets an origin from which its outgoing arcs are reached
+/// without a turn, so searches can start at a vertex rather than an arc.
+pub struct EdgeBasedGraph {
+    /// Tail and head of every arc, indexed by arc id.
+    arcs: Vec<(Vertex, Vertex)>,
+    /// The line graph with the arcs followed by the origins.
+    lines: NeighborList,
+}
+
+impl EdgeBasedGraph {
+    /// Constructs the line graph of `n` vertices connected by `edges` under the given turn costs.
+    pub fn new(n: usize, edges: impl Iterator<Item = Edge>, turns: &TurnCosts) -> Self {
+        let edges: Vec<Edge> = edges.collect();
+        let m = edges.len();
+        let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (id, e) in edges.iter().enumerate() {
+            outgoing[usize::from(e.from)].push(id);
+        }
+
+        let mut lines: NeighborList = Vec::with_capacity(m + n);
+        for e in edges.iter() {
+            let turns_from = outgoing[usize::from(e.to)]
+                .iter()
+                .filter_map(|&next| {
+                    let onto = &edges[next];
+                    let cost = turns.get(e.from, e.to, onto.to)?;
+                    Some(Neighbor {
+                        to: Vertex::try_from(next).unwrap(),
+                        weight: onto.weight + cost,
+                    })
+                })
+                .collect();
+            lines.push(turns_from);
+        }
+        for out in outgoing.iter() {
+            lines.push(
+                out.iter()
+                    .map(|&next| Neighbor {
+                        to: Vertex::try_from(next).unwrap(),
+                        weight: edges[next].weight,
+                    })
+                    .collect(),
+            );
+        }
+
+        Self {
+            arcs: edges.iter().map(|e| (e.from, e.to)).collect(),
+            lines,
+        }
+    }
+
+    /// Number of vertices of the line graph, used to size the search structures.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Gets the line graph vertex a search from `source` has to be started at.
+    #[inline]
+    pub fn origin(&self, source: Vertex) -> Vertex {
+        Vertex::try_from(self.arcs.len() + usize::from(source)).unwrap()
+    }
+
+    /// Translates a line graph vertex back into the original vertex it ends at.
+    #[inline]
+    fn head(&self, line: Vertex) -> Vertex {
+        let index = usize::from(line);
+        match self.arcs.get(index) {
+            Some((_, head)) => *head,
+            None => Vertex::try_from(index - self.arcs.len()).unwrap(),
+        }
+    }
+}
+
+/// Performs edge-based shortest path computation to a specific target.
+///
+/// The search has to be initialized at `graph.origin(source)` with `graph.size()`.
+/// The returned route is expressed in vertices of the original graph.
+#[inline]
+pub fn sp_turns<D>(mut source: D, target: Vertex, graph: &EdgeBasedGraph) -> Option<(u32, Route)>
+where
+    D: Dijkstra,
+{
+    while let Some((dist, u)) = source.pop_min() {
+        if graph.head(u.into()) == target {
+            //can safely unwrap because the arc would have appeared if a path did't exist
+            let lines = source.get_path(u.into()).unwrap();
+            let route = lines.0.into_iter().map(|line| graph.head(line)).collect();
+            return Some((dist.into(), Route(route)));
+        }
+        // update turns out of u
+        for e in graph.lines.get_neighbors(u.into()) {
+            source.explore(u, dist, e);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::dijkstra::{sp_naiv, OwnedLookup, StructuredEdges};
+    use crate::dimacs::tests::random_edges;
+    use crate::implicit_heaps::BinaryHeap;
+
+    /// Sums up the arc lengths and turn costs along a route.
+    fn route_cost(route: &Route, edges: &[Edge], turns: &TurnCosts) -> Option<u32> {
+        let path: Vec<Vertex> = route.0.iter().rev().copied().collect();
+        let mut cost = 0;
+        for (i, pair) in path.windows(2).enumerate() {
+            cost += edges
+                .iter()
+                .filter(|e| e.from == pair[0] && e.to == pair[1])
+                .map(|e| e.weight)
+                .min()?;
+            if i > 0 {
+                cost += turns.get(path[i - 1], pair[0], pair[1])?;
+            }
+        }
+        Some(cost)
+    }
+
+    #[test]
+    fn parse_turns() {
+        assert_eq!(
+            Turn::from_str("t 1 2 3 40"),
+            Ok(Turn {
+                from: Vertex(1),
+                via: Vertex(2),
+                to: Vertex(3),
+                cost: Some(40)
+            })
+        );
+        assert_eq!(
+            Turn::from_str("r 3 2 1").unwrap().cost,
+            None,
+            "restrictions forbid the turn"
+        );
+        assert!(Turn::from_str("c some comment").is_err());
+        assert!(Turn::from_str("t 1 2 3").is_err());
+    }
+
+    #[test]
+    fn no_turn_costs_match_vertex_based() {
+        let n = 200;
+        let size = n + 1;
+        let edges = random_edges(n, 4 * n);
+        let graph: NeighborList = StructuredEdges::new(size, edges.clone().into_iter());
+        let line_graph = EdgeBasedGraph::new(size, edges.into_iter(), &TurnCosts::default());
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let s = Vertex(rng.gen_range(1..=n as u32));
+            let t = Vertex(rng.gen_range(1..=n as u32));
+            let source: OwnedLookup<BinaryHeap> = OwnedLookup::from((s, size));
+            let (expected, _) = sp_naiv(source, t, &graph).unwrap();
+            let source: OwnedLookup<BinaryHeap> =
+                OwnedLookup::from((line_graph.origin(s), line_graph.size()));
+            let (dist, route) = sp_turns(source, t, &line_graph).unwrap();
+            assert_eq!(dist, expected);
+            assert_eq!(route.0.first(), Some(&t));
+            assert_eq!(route.0.last(), Some(&s));
+        }
+    }
+
+    #[test]
+    fn turn_restrictions() {
+        // 1 -> 2 -> 3 is short but turning left at 2 is forbidden,
+        // the detour 1 -> 2 -> 4 -> 2 -> 3 needs a U-turn at 4.
+        let edges = vec![
+            Edge {
+                from: Vertex(1),
+                to: Vertex(2),
+                weight: 1,
+            },
+            Edge {
+                from: Vertex(2),
+                to: Vertex(3),
+                weight: 1,
+            },
+            Edge {
+                from: Vertex(2),
+                to: Vertex(4),
+                weight: 5,
+            },
+            Edge {
+                from: Vertex(4),
+                to: Vertex(2),
+                weight: 5,
+            },
+            Edge {
+                from: Vertex(1),
+                to: Vertex(3),
+                weight: 30,
+            },
+        ];
+        let size = 5;
+        let mut turns: TurnCosts = [Turn {
+            from: Vertex(1),
+            via: Vertex(2),
+            to: Vertex(3),
+            cost: None,
+        }]
+        .into_iter()
+        .collect();
+        turns.u_turn = Some(3);
+
+        let line_graph = EdgeBasedGraph::new(size, edges.clone().into_iter(), &turns);
+        let source: OwnedLookup<BinaryHeap> =
+            OwnedLookup::from((line_graph.origin(Vertex(1)), line_graph.size()));
+        let (dist, route) = sp_turns(source, Vertex(3), &line_graph).unwrap();
+        assert_eq!(dist, 1 + 5 + 3 + 5 + 1);
+        assert_eq!(
+            route.0,
+            vec![Vertex(3), Vertex(2), Vertex(4), Vertex(2), Vertex(1)]
+        );
+        assert_eq!(route_cost(&route, &edges, &turns), Some(dist));
+
+        turns.u_turn = None;
+        let line_graph = EdgeBasedGraph::new(size, edges.clone().into_iter(), &turns);
+        let source: OwnedLookup<BinaryHeap> =
+            OwnedLookup::from((line_graph.origin(Vertex(1)), line_graph.size()));
+        let (dist, route) = sp_turns(source, Vertex(3), &line_graph).unwrap();
+        assert_eq!(dist, 30);
+        assert_eq!(route.0, vec![Vertex(3), Vertex(1)]);
+    }
+}